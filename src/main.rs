@@ -1,17 +1,19 @@
 #![allow(non_snake_case)]
 #![feature(async_closure)]
 
-use std::collections::HashSet;
-use std::io::{stdout, Stdout};
+use std::collections::HashMap;
+use std::io::stdout;
 use std::path::Path;
 use std::result::Result::Ok;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
 use crossterm::{
     event::{self, EnableMouseCapture, Event as CEvent, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen},
 };
+use futures::future::BoxFuture;
 use log::error;
 use log::info;
 use log::LevelFilter;
@@ -23,17 +25,47 @@ use log4rs::{
     config::{Appender, Config, Root},
     encode::pattern::PatternEncoder,
 };
-use sysinfo::{System, SystemExt};
+use tokio::sync::Notify;
 use tokio::time::{sleep, Duration};
 use tui::{backend::CrosstermBackend, Terminal};
 
-use crate::torrentLib::request::TorrentAction;
+use crate::scheduler::{Job, JobFn, Scheduler};
+use crate::store::{RemovedTorrent, Store};
+use crate::tasks::budget::MagicBudget;
+use crate::tasks::promote::{FeedEntry, Verdict, BY_HAND};
+use crate::tasks::removal::Removal;
+use crate::tasks::rules::RuleEngine;
+use crate::tasks::scope::Scope;
+use crate::tasks::ucoin::UCoinLedger;
+use crate::torrentLib::response::{SessionStats, Torrent};
+use crate::u2client::cache::DetailCache;
 use crate::u2client::client::U2client;
-use crate::u2client::types::Status;
-use crate::ui::TabsState;
+use crate::u2client::limiter::Limiter;
+use crate::u2client::types::{Status, UserInfo};
+use crate::ui::actions::{Command, Toast, TorrentCommand};
+use crate::ui::detail::TorrentDetail;
+use crate::ui::feed::{FeedAction, FeedCommand, FeedTable, FeedView};
+use crate::ui::history::{History, HistoryGraph, Window};
+use crate::ui::log::{LogLine, LogTable, LogView};
+use crate::ui::sampler::{HardwareSampler, LogTail, LOG_LINES};
+use crate::ui::torrents::{TorrentRow, TorrentTable, TorrentView};
+use crate::ui::{Cached, TabsState};
+
+/// how often the dashboard samples the hardware and checks the log
+const HARDWARE_INTERVAL: Duration = Duration::from_secs(2);
+const LOG_INTERVAL: Duration = Duration::from_secs(1);
+/// log files kept by the roller besides main.log
+const LOG_ARCHIVES: u32 = 1;
+/// how often an open detail pane is fetched again
+const DETAIL_INTERVAL: Duration = Duration::from_secs(2);
+/// how often the history graphs are rebuilt
+const HISTORY_INTERVAL: Duration = Duration::from_secs(2);
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
+pub mod scheduler;
+pub mod store;
+pub mod tasks;
 pub mod torrentLib;
 pub mod u2client;
 pub mod ui;
@@ -53,6 +85,8 @@ async fn main() -> Result<()> {
             &args.RpcUsername,
             &args.RpcPassword,
             &args.workRoot,
+            Limiter::new(&args),
+            DetailCache::new(&args),
         )
         .await?,
     );
@@ -69,14 +103,17 @@ async fn main() -> Result<()> {
 
     let mainDir = format!("{}/main.log", root);
     let archivedDir = format!("{}/{}.log", root, "{}");
+    let archives: Vec<String> = (0..LOG_ARCHIVES)
+        .map(|i| format!("{}/{}.log", root, i))
+        .collect();
 
-    let roller = FixedWindowRoller::builder().build(&archivedDir, 1)?;
+    let roller = FixedWindowRoller::builder().build(&archivedDir, LOG_ARCHIVES)?;
     let trigger = SizeTrigger::new(100_000);
     let policy = CompoundPolicy::new(Box::new(trigger), Box::new(roller));
 
     let appender = RollingFileAppender::builder()
         .encoder(Box::new(PatternEncoder::new(
-            "{d(%Y-%m-%d %H:%M:%S)} - {m}\n",
+            "{d(%Y-%m-%d %H:%M:%S)} {l} - {m}\n",
         )))
         .build(&mainDir, Box::new(policy))?;
 
@@ -86,173 +123,298 @@ async fn main() -> Result<()> {
 
     let _ = log4rs::init_config(config)?;
 
-    let lastLocal = Arc::new(RwLock::new(None));
-    let lastRemote = Arc::new(RwLock::new(None));
-    let mask = Arc::new(RwLock::new(0u8));
-    let tabStatus = Arc::new(RwLock::new(TabsState::new()));
+    let budget = MagicBudget::new(&args);
+    let ctx = Arc::new(Context {
+        rules: RuleEngine::new(&args)?,
+        removal: Removal::new(&args)?,
+        scope: Scope::new(&args)?,
+        store: Store::open(&format!("{}/u2.db", args.workRoot))?,
+        budget: Mutex::new(budget),
+        ledger: RwLock::new(UCoinLedger::new()),
+        lastLocal: RwLock::new(None),
+        lastRemote: RwLock::new(None),
+        history: RwLock::new(History::new()),
+        torrents: RwLock::new(None),
+        torrentsGen: AtomicU64::new(0),
+        feed: RwLock::new(None),
+        feedGen: AtomicU64::new(0),
+        mask: RwLock::new(0u8),
+        agent,
+        args,
+    });
+    if let (Ok(mut ledger), Ok(mut budget)) = (ctx.ledger.write(), ctx.budget.lock()) {
+        let spend = ledger.reload(&ctx.store, chrono::Local::now().timestamp())?;
+        budget.sync(0, spend);
+    }
 
-    let agentSep = Arc::clone(&agent);
-    let agentSep2 = Arc::clone(&agent);
-    let lastLocalSep = Arc::clone(&lastLocal);
-    let lastRemoteSep = Arc::clone(&lastRemote);
-    let maskSep = Arc::clone(&mask);
-    let tabStatusSep = Arc::clone(&tabStatus);
+    let mut scheduler = Scheduler::new(Arc::clone(&ctx));
+    let jobs: [(&str, &str, JobFn<Context>); 6] = [
+        ("promote", "60s", promote),
+        ("backEnd", "2s", backEnd),
+        ("maintain", "5m", maintain),
+        ("expire", "60s", expire),
+        ("ucoin", "30m", ucoin),
+        ("snapshot", "5m", snapshot),
+    ];
+    for (name, schedule, run) in jobs.iter() {
+        let config = ctx.args.jobs.as_ref().and_then(|x| x.get(*name));
+        scheduler.add(Job::new(name, schedule, config)?, *run);
+    }
+    let jobStatus = scheduler.status();
 
-    enable_raw_mode()?;
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
-    terminal.clear()?;
-    info!("init done");
+    let tabStatus = Arc::new(RwLock::new(TabsState::new()));
+    let tabStatusSep = Arc::clone(&tabStatus);
+    // set by key and resize events, the dashboard is otherwise drawn only when what it shows changed
+    let redraw = Arc::new(AtomicBool::new(true));
+    let redrawSep = Arc::clone(&redraw);
+    let torrentView = Arc::new(RwLock::new(TorrentView::default()));
+    let torrentViewSep = Arc::clone(&torrentView);
+    let feedView = Arc::new(RwLock::new(FeedView::default()));
+    let feedViewSep = Arc::clone(&feedView);
+    let logView = Arc::new(RwLock::new(LogView::default()));
+    let logViewSep = Arc::clone(&logView);
+    let ctxSep = Arc::clone(&ctx);
 
-    let downdloadFxFilter = args.downloadFxFilter.to_owned().unwrap_or(0.0);
-    let GBSizeFilter = args.GBSizeFilter.to_owned().unwrap_or(0.0);
-
-    let promote = tokio::task::spawn(async move {
-        let handleOne = async || -> Result<()> {
-            let feed = agent.getDownloadList();
-            let mut torrentList: HashSet<String> = HashSet::new();
-            let working = agent.getWorkingTorrent();
-            let (feed, working) = futures::future::join(feed, working).await;
-            let feed = feed?;
-            let working = working?.torrents;
-
-            for x in working.into_iter() {
-                let x = x.hash_string.ok_or("handleOne:bad torrent hash")?;
-                torrentList.insert(x);
+    let detail = Arc::new(RwLock::new(None));
+    let detailSep = Arc::clone(&detail);
+    let detailWake = Arc::new(Notify::new());
+    let detailWakeSep = Arc::clone(&detailWake);
+    let toast: Arc<RwLock<Option<Toast>>> = Arc::new(RwLock::new(None));
+    let toastSep = Arc::clone(&toast);
+    let torrentViewDetail = Arc::clone(&torrentView);
+    let ctxDetail = Arc::clone(&ctx);
+    let detailFetcher = tokio::task::spawn(async move {
+        loop {
+            let hash = match torrentViewDetail.read() {
+                Ok(x) if x.detail => x.selected.clone(),
+                _ => None,
+            };
+            let x = match hash {
+                Some(hash) => match fetchDetail(&ctxDetail, &hash).await {
+                    Ok(x) => Some(x),
+                    Err(x) => {
+                        error!("detail:{}", x);
+                        None
+                    }
+                },
+                None => None,
+            };
+            if let Ok(mut detail) = detailSep.write() {
+                *detail = x;
             }
+            tokio::select! {
+                _ = sleep(DETAIL_INTERVAL) => {}
+                _ = detailWakeSep.notified() => {}
+            }
+        }
+    });
 
-            let torrentListRef = &torrentList;
-            let agentRef = &agent;
-
-            let work = feed.iter().map(async move |i| -> Result<()> {
-                if i.U2Info.seeder == 0
-                    || torrentListRef.contains(&i.U2Info.Hash)
-                    || i.U2Info.avgProgress > 0.5
-                {
-                    Ok(())
-                } else {
-                    if i.U2Info.downloadFX > downdloadFxFilter && i.U2Info.GbSize > GBSizeFilter {
-                        let time = (i.U2Info.GbSize * 1024.0 / 5.0 / 3600.0).floor() as i32 + 1;
-                        let x = agentRef.applyMagic(&i.uid, time, 5).await;
-                        if x.is_err() {
-                            return x;
-                        } else {
-                            info!("apply magic on:{}", &i.title);
+    let window = Arc::new(RwLock::new(Window::default()));
+    let windowSep = Arc::clone(&window);
+    let history = Arc::new(RwLock::new(None));
+    let historySep = Arc::clone(&history);
+    let historyWake = Arc::new(Notify::new());
+    let historyWakeSep = Arc::clone(&historyWake);
+    let ctxHistory = Arc::clone(&ctx);
+    let tabHistory = Arc::clone(&tabStatus);
+    tokio::task::spawn(async move {
+        loop {
+            // only the BT tab shows the graphs
+            let shown = tabHistory.read().map(|x| x.index == 1).unwrap_or(false);
+            if shown {
+                let window = window.read().map(|x| *x).unwrap_or_default();
+                let ctx = Arc::clone(&ctxHistory);
+                // the snapshots are read with rusqlite, off the runtime threads
+                match tokio::task::spawn_blocking(move || historyGraph(&ctx, window)).await {
+                    Ok(x) => {
+                        if let Ok(mut history) = historySep.write() {
+                            *history = x;
                         }
                     }
-                    info!("promote:new job:{},{} GB", &i.title, &i.U2Info.GbSize);
-                    agentRef.addTorrent(&i.url).await
+                    // a panic loses one sample, not the sampler
+                    Err(x) => error!("historySampler:{}", x),
                 }
-            });
-            let res = futures::future::join_all(work).await;
-            for i in res.into_iter() {
-                let _ = i?;
             }
-            info!("promote:done");
-            Ok(())
-        };
+            tokio::select! {
+                _ = sleep(HISTORY_INTERVAL) => {}
+                _ = historyWakeSep.notified() => {}
+            }
+        }
+    });
+
+    let hardware = Arc::new(RwLock::new(None));
+    let hardwareSep = Arc::clone(&hardware);
+    tokio::task::spawn(async move {
+        let mut sampler = HardwareSampler::new();
         loop {
-            match handleOne().await {
-                Ok(_) => {}
+            // sysinfo reads /proc and /sys, off the runtime threads
+            let (x, back) = match tokio::task::spawn_blocking(move || {
+                let x = sampler.sample();
+                (x, sampler)
+            })
+            .await
+            {
+                Ok((x, back)) => (Some(x), back),
+                // the sampler went down with the panic, start over
                 Err(x) => {
-                    error!("promote:{}", x);
+                    error!("hardwareSampler:{}", x);
+                    (None, HardwareSampler::new())
                 }
+            };
+            sampler = back;
+            if let (Some(x), Ok(mut hardware)) = (x, hardwareSep.write()) {
+                *hardware = Some(x);
             }
-            sleep(Duration::from_secs(60)).await;
+            sleep(HARDWARE_INTERVAL).await;
         }
     });
 
-    let backEnd = tokio::task::spawn(async move {
+    let log = Arc::new(RwLock::new(None));
+    let logSep = Arc::clone(&log);
+    let logKeys = Arc::clone(&log);
+    // bumped whenever `log` changes
+    let logGen = Arc::new(AtomicU64::new(0));
+    let logGenSep = Arc::clone(&logGen);
+    let mut tail = LogTail::withArchives(&mainDir, &archives, LOG_LINES);
+    tokio::task::spawn(async move {
         loop {
-            let mut masks = 0u8;
-            let remote = agentSep.getUserInfo();
-            let local = agentSep.getStats();
-            let (remote, local) = tokio::join!(remote, local);
-            if let Ok(x) = remote {
-                if let Ok(mut lastRemote) = lastRemote.write() {
-                    *lastRemote = Some(x)
-                } else {
-                    error!("backEnd:get remote lock failed");
+            // reads the log files
+            let (x, back) = match tokio::task::spawn_blocking(move || {
+                let x = tail.poll();
+                (x, tail)
+            })
+            .await
+            {
+                Ok(x) => x,
+                // the tail went down with the panic, start over
+                Err(x) => {
+                    error!("logSampler:{}", x);
+                    (
+                        Ok(None),
+                        LogTail::withArchives(&mainDir, &archives, LOG_LINES),
+                    )
                 }
-                masks |= 2;
-            } else {
-                error!("backEnd:get U2 Info failed");
-            }
-
-            if let Ok(x) = local {
-                if let Ok(mut lastLocal) = lastLocal.write() {
-                    *lastLocal = Some(x)
-                } else {
-                    error!("backEnd:get local lock failed");
+            };
+            tail = back;
+            match x {
+                Ok(Some(x)) => {
+                    let x: Vec<LogLine> = x.iter().map(|x| LogLine::parse(x)).collect();
+                    if let Ok(mut log) = logSep.write() {
+                        *log = Some(x);
+                    }
+                    logGenSep.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(None) => {}
+                Err(_) => {
+                    if let Ok(mut log) = logSep.write() {
+                        *log = None;
+                    }
+                    logGenSep.fetch_add(1, Ordering::SeqCst);
                 }
-                masks |= 1;
-            } else {
-                error!("backEnd:get BT local info failed");
-            }
-            if let Ok(mut mask) = mask.write() {
-                *mask = masks;
-            } else {
-                error!("backEnd:get masks lock failed");
             }
-            sleep(Duration::from_secs(2)).await;
+            sleep(LOG_INTERVAL).await;
         }
     });
 
+    enable_raw_mode()?;
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    terminal.clear()?;
+    info!("init done");
+
+    let dryRun = ctx.args.dryRun.unwrap_or(false);
     let frontEnd = tokio::task::spawn(async move {
-        let handleOne = |T: &mut Terminal<CrosstermBackend<Stdout>>| -> Result<()> {
+        let mut torrentTable = Cached::new();
+        let mut feedTable = Cached::new();
+        let mut logTable = Cached::new();
+        let mut current = || -> (usize, u8, Status) {
             let idx = match tabStatus.read() {
-                Ok(tabStatus) => tabStatus.index as i8,
-                _ => -1,
+                Ok(tabStatus) => tabStatus.index,
+                _ => 0,
             };
-            let masks = match maskSep.read() {
+            let masks = match ctx.mask.read() {
                 Ok(x) => *x,
                 _ => 0,
             };
+            let mut status = Status {
+                hardware: None,
+                local: None,
+                history: None,
+                torrents: None,
+                detail: None,
+                feed: None,
+                remote: None,
+                ucoin: None,
+                log: None,
+                jobs: None,
+                cache: None,
+                toast: toast
+                    .read()
+                    .ok()
+                    .and_then(|x| x.clone())
+                    .filter(|x| !x.expired()),
+                dryRun,
+                sitePaused: ctx.agent.pausedFor().map(|x| x.as_secs()),
+            };
             match idx {
                 0 => {
-                    let status = Status {
-                        hardware: Some(System::new_all()),
-                        local: None,
-                        remote: match lastRemoteSep.read() {
-                            Ok(lastRemoteSep) => lastRemoteSep.as_ref().cloned(),
-                            Err(_) => None,
-                        },
-                        logDir: None,
-                    };
-                    T.draw(|f| ui::draw(f, status, masks, 0))?;
+                    status.hardware = hardware.read().ok().and_then(|x| x.clone());
+                    status.remote = ctx.lastRemote.read().ok().and_then(|x| x.clone());
+                    status.ucoin = ctx.ledger.read().ok().map(|x| x.today());
+                    status.jobs = jobStatus.read().ok().map(|x| x.clone());
+                    status.cache = Some(ctx.agent.cacheStats());
                 }
                 1 => {
-                    let status = Status {
-                        hardware: None,
-                        local: match lastLocalSep.read() {
-                            Ok(lastLocalSep) => lastLocalSep.as_ref().cloned(),
-                            Err(_) => None,
-                        },
-                        remote: None,
-                        logDir: None,
-                    };
-                    T.draw(|f| ui::draw(f, status, masks, 1))?;
+                    status.local = ctx.lastLocal.read().ok().and_then(|x| x.clone());
+                    status.history = history.read().ok().and_then(|x| x.clone());
                 }
                 2 => {
-                    let status = Status {
-                        hardware: None,
-                        local: None,
-                        remote: None,
-                        logDir: Some(mainDir.to_owned()),
-                    };
-                    T.draw(|f| ui::draw(f, status, masks, 2))?;
+                    if let Ok(view) = torrentView.read() {
+                        let key = (ctx.torrentsGen.load(Ordering::SeqCst), view.clone());
+                        status.torrents = torrentTable.get(key, || {
+                            let torrents = ctx.torrents.read().ok()?;
+                            torrents.as_ref().map(|x| TorrentTable::new(x, &view))
+                        });
+                        if view.detail {
+                            status.detail = detail.read().ok().and_then(|x| x.clone());
+                        }
+                    }
+                }
+                3 => {
+                    if let Ok(view) = feedView.read() {
+                        let key = (ctx.feedGen.load(Ordering::SeqCst), view.clone());
+                        status.feed = feedTable.get(key, || {
+                            let feed = ctx.feed.read().ok()?;
+                            feed.as_ref().map(|x| FeedTable {
+                                entries: x.clone(),
+                                view: view.clone(),
+                            })
+                        });
+                    }
+                }
+                4 => {
+                    if let Ok(view) = logView.read() {
+                        let key = (logGen.load(Ordering::SeqCst), view.clone());
+                        status.log = logTable.get(key, || {
+                            let log = log.read().ok()?;
+                            log.as_ref().map(|x| LogTable::new(x, &view))
+                        });
+                    }
                 }
                 _ => {}
             }
-            Ok(())
+            (idx, masks, status)
         };
+        let mut last = None;
         loop {
-            match handleOne(&mut terminal) {
-                Ok(_) => {}
-                Err(x) => {
+            let now = current();
+            if redraw.swap(false, Ordering::SeqCst) || last.as_ref() != Some(&now) {
+                let (idx, masks, status) = now.clone();
+                if let Err(x) = terminal.draw(|f| ui::draw(f, status, masks, idx)) {
                     error!("frontEnd:{}", x);
                 }
+                last = Some(now);
             }
             sleep(Duration::from_millis(50)).await;
         }
@@ -266,23 +428,98 @@ async fn main() -> Result<()> {
                 .checked_sub(last_tick.elapsed())
                 .unwrap_or_else(|| Duration::from_secs(0));
             if event::poll(timeout).unwrap() {
-                if let CEvent::Key(key) = event::read().unwrap() {
-                    match key.code {
-                        KeyCode::Char('q') => {
-                            break;
-                        }
-                        KeyCode::Left => {
-                            if let Ok(mut x) = tabStatusSep.write() {
-                                (*x).previous();
-                            }
-                        }
-                        KeyCode::Right => {
-                            if let Ok(mut x) = tabStatusSep.write() {
-                                (*x).next();
-                            }
+                match event::read().unwrap() {
+                    CEvent::Key(key) => {
+                        let tab = match tabStatusSep.read() {
+                            Ok(x) => x.index,
+                            Err(_) => 0,
+                        };
+                        // the tab gets the key first, the rest are global
+                        let handled = match tab {
+                            1 => match (key.code, windowSep.write()) {
+                                (KeyCode::Char('w'), Ok(mut window)) => {
+                                    *window = window.next();
+                                    historyWake.notify_one();
+                                    true
+                                }
+                                _ => false,
+                            },
+                            2 => match (ctxSep.torrents.read(), torrentViewSep.write()) {
+                                (Ok(torrents), Ok(mut view)) => {
+                                    let selected = (view.detail, view.selected.clone());
+                                    let handled = view
+                                        .handleKey(key.code, torrents.as_deref().unwrap_or(&[]));
+                                    if view.detail
+                                        && (view.detail, view.selected.clone()) != selected
+                                    {
+                                        detailWake.notify_one();
+                                    }
+                                    if let Some(x) = view.command.take() {
+                                        let ctx = Arc::clone(&ctxSep);
+                                        let toast = Arc::clone(&toastSep);
+                                        let detailWake = Arc::clone(&detailWake);
+                                        tokio::task::spawn(async move {
+                                            let x = perform(&ctx, &x).await;
+                                            if let Ok(mut toast) = toast.write() {
+                                                *toast = Some(x);
+                                            }
+                                            detailWake.notify_one();
+                                        });
+                                    }
+                                    handled
+                                }
+                                _ => false,
+                            },
+                            3 => match (ctxSep.feed.read(), feedViewSep.write()) {
+                                (Ok(feed), Ok(mut view)) => {
+                                    let handled =
+                                        view.handleKey(key.code, feed.as_deref().unwrap_or(&[]));
+                                    if let Some(x) = view.command.take() {
+                                        let ctx = Arc::clone(&ctxSep);
+                                        let toast = Arc::clone(&toastSep);
+                                        tokio::task::spawn(async move {
+                                            let x = performFeed(&ctx, &x).await;
+                                            if let Ok(mut toast) = toast.write() {
+                                                *toast = Some(x);
+                                            }
+                                        });
+                                    }
+                                    handled
+                                }
+                                _ => false,
+                            },
+                            4 => match (logKeys.read(), logViewSep.write()) {
+                                (Ok(log), Ok(mut view)) => {
+                                    view.handleKey(key.code, log.as_deref().unwrap_or(&[]))
+                                }
+                                _ => false,
+                            },
+                            _ => false,
+                        };
+                        if !handled {
+                            match key.code {
+                                KeyCode::Char('q') => {
+                                    break;
+                                }
+                                KeyCode::Left => {
+                                    if let Ok(mut x) = tabStatusSep.write() {
+                                        (*x).previous();
+                                    }
+                                    historyWake.notify_one();
+                                }
+                                KeyCode::Right => {
+                                    if let Ok(mut x) = tabStatusSep.write() {
+                                        (*x).next();
+                                    }
+                                    historyWake.notify_one();
+                                }
+                                _ => {}
+                            };
                         }
-                        _ => {}
-                    };
+                        redrawSep.store(true, Ordering::SeqCst);
+                    }
+                    CEvent::Resize(_, _) => redrawSep.store(true, Ordering::SeqCst),
+                    _ => {}
                 }
             }
             if last_tick.elapsed() >= tick_rate {
@@ -290,65 +527,333 @@ async fn main() -> Result<()> {
             }
         }
     });
-    let MAX_SIZE = args.maxSize;
-
-    let maintain = tokio::task::spawn(async move {
-        let handleOne = async || -> Result<()> {
-            let now = agentSep2.getWorkingTorrent().await?.torrents;
-            let mut tot = 0f32;
-            for i in now.iter() {
-                let sb = i.total_size.unwrap_or(0) as f32 / 1e9;
-                tot += sb;
+    let _ = tokio::select! {
+        _ = frontEnd => {}
+        _ = keyboard => {}
+        _ = detailFetcher => {}
+        _ = scheduler.run() => {}
+    };
+    disable_raw_mode()?;
+    Ok(())
+}
+
+/// everything the scheduled jobs share
+struct Context {
+    agent: Arc<U2client>,
+    args: u2client::types::Config,
+    rules: RuleEngine,
+    removal: Removal,
+    scope: Scope,
+    store: Store,
+    budget: Mutex<MagicBudget>,
+    ledger: RwLock<UCoinLedger>,
+    lastLocal: RwLock<Option<SessionStats>>,
+    lastRemote: RwLock<Option<UserInfo>>,
+    /// the last hour of speeds and U2 totals
+    history: RwLock<History>,
+    torrents: RwLock<Option<Vec<TorrentRow>>>,
+    /// bumped whenever `torrents` changes
+    torrentsGen: AtomicU64,
+    /// what the last promote cycle made of the feed
+    feed: RwLock<Option<Vec<FeedEntry>>>,
+    /// bumped whenever `feed` changes
+    feedGen: AtomicU64,
+    mask: RwLock<u8>,
+}
+
+/// a torrent with its files, peers and trackers, and what U2 says about it
+async fn fetchDetail(ctx: &Arc<Context>, hash: &str) -> Result<TorrentDetail> {
+    let torrent = ctx.agent.getTorrentDetail(hash).await?;
+    let uid = {
+        let ctx = Arc::clone(ctx);
+        let hash = hash.to_string();
+        // the store is read with rusqlite, off the runtime threads; later lookups hit the index
+        tokio::task::spawn_blocking(move || ctx.agent.uidOf(&hash, &ctx.store)).await??
+    };
+    let info = match uid {
+        Some(ref uid) => match ctx.agent.getTorrentInfo(uid).await {
+            Ok(x) => Some(x),
+            Err(_) => ctx.agent.cachedInfo(hash, &ctx.store),
+        },
+        None => None,
+    };
+    Ok(TorrentDetail { torrent, uid, info })
+}
+
+/// sends a command from the torrent list, the toast tells how it went
+async fn perform(ctx: &Context, x: &TorrentCommand) -> Toast {
+    let what = x.describe();
+    if ctx.args.dryRun.unwrap_or(false) {
+        info!("torrents:dry run, would {}", what);
+        return Toast::new(format!("dry run, would {}", what), true);
+    }
+    let res = match x.command {
+        Command::Action(op) => {
+            ctx.agent
+                .performActionOnTorrent(x.hash.to_owned(), op)
+                .await
+        }
+        Command::Remove(deleteData) => {
+            match ctx.agent.removeTorrent(x.hash.to_owned(), deleteData).await {
+                Ok(_) => ctx.store.recordRemoved(&RemovedTorrent {
+                    time: chrono::Local::now().timestamp(),
+                    hash: x.hash.to_owned(),
+                    name: x.name.to_owned(),
+                    size: x.size,
+                    reason: "removed by hand".to_string(),
+                }),
+                Err(e) => Err(e),
             }
-            if tot > MAX_SIZE {
-                let V = agentSep2.getRemove().await?;
-                let mut all = Vec::new();
-                for i in V.into_iter() {
-                    info!(
-                        "maintain:remove {}, {} GB",
-                        i.name.ok_or("handleOne:broken name")?,
-                        i.total_size.ok_or("handleOne:broken size")? as f32 / 1e9
-                    );
-                    let hash = i.hash_string.ok_or("handleOne:broken hash")?;
-                    all.push(agentSep2.removeTorrent(hash));
-                }
-                let res = futures::future::join_all(all).await;
-                for i in res.into_iter() {
-                    let _ = i?;
+        }
+    };
+    match res {
+        Ok(_) => {
+            info!("torrents:{}", what);
+            Toast::new(format!("{}: success", what), true)
+        }
+        Err(e) => {
+            error!("torrents:{} failed, {}", what, e);
+            Toast::new(format!("{}: {}", what, e), false)
+        }
+    }
+}
+
+/// adds a feed item or casts magic on it by hand, the toast tells how it went
+async fn performFeed(ctx: &Context, x: &FeedCommand) -> Toast {
+    let what = x.describe();
+    let dryRun = ctx.args.dryRun.unwrap_or(false);
+    let res = match x.action {
+        FeedAction::Add => tasks::promote::addByHand(&ctx.agent, &ctx.args, &ctx.store, &x.item)
+            .await
+            .map(|_| {
+                if let Ok(mut feed) = ctx.feed.write() {
+                    for i in feed.iter_mut().flatten() {
+                        if i.item.uid == x.item.uid {
+                            i.verdict = Verdict::Added {
+                                rule: BY_HAND.to_string(),
+                                magic: None,
+                                dryRun,
+                            };
+                        }
+                    }
                 }
+                ctx.feedGen.fetch_add(1, Ordering::SeqCst);
+                "success".to_string()
+            }),
+        FeedAction::Magic => {
+            tasks::promote::magicByHand(&ctx.agent, &ctx.args, &ctx.budget, &ctx.store, &x.item)
+                .await
+                .map(|cost| format!("{:.0} UCoin", cost))
+        }
+    };
+    match res {
+        Ok(x) => {
+            let mut text = if dryRun {
+                format!("dry run, would {}", what)
+            } else {
+                format!("{}: success", what)
+            };
+            if !x.is_empty() {
+                text.push_str(&format!(", {}", x));
+            }
+            Toast::new(text, true)
+        }
+        Err(e) => {
+            error!("feed:{} failed, {}", what, e);
+            Toast::new(format!("{}: {}", what, e), false)
+        }
+    }
+}
+
+/// jobs talking to U2 skip their runs while the site looks down
+fn sitePaused(ctx: &Context, job: &str) -> bool {
+    match ctx.agent.pausedFor() {
+        Some(x) => {
+            info!("{}:site down, skipped for {}s", job, x.as_secs());
+            true
+        }
+        None => false,
+    }
+}
+
+fn promote(ctx: Arc<Context>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        if sitePaused(&ctx, "promote") {
+            return Ok(());
+        }
+        let x = tasks::promote::handleOne(
+            &ctx.agent,
+            &ctx.args,
+            &ctx.rules,
+            &ctx.budget,
+            &ctx.store,
+            &ctx.feed,
+        )
+        .await;
+        ctx.feedGen.fetch_add(1, Ordering::SeqCst);
+        x
+    })
+}
+
+/// the graphs of `window`, the snapshots filling in what is older than the samples in memory
+fn historyGraph(ctx: &Context, window: Window) -> Option<HistoryGraph> {
+    let now = chrono::Local::now().timestamp();
+    let since = now - window.seconds();
+    let covered = ctx.history.read().ok()?.coveredSince();
+    let (session, user) = if covered.map_or(true, |x| x > since) {
+        (
+            ctx.store.sessionSince(since).unwrap_or_else(|x| {
+                error!("historyGraph:{}", x);
+                Vec::new()
+            }),
+            ctx.store.userSince(since).unwrap_or_else(|x| {
+                error!("historyGraph:{}", x);
+                Vec::new()
+            }),
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    ctx.history
+        .read()
+        .ok()
+        .map(|x| x.graph(window, now, &session, &user))
+}
+
+/// the rows of the torrent list, with the categories and promotions the tool knows of
+fn torrentRows(ctx: &Context, torrents: &[Torrent]) -> Vec<TorrentRow> {
+    let hashes: Vec<&str> = torrents
+        .iter()
+        .filter_map(|x| x.hash_string.as_deref())
+        .collect();
+    let categories = ctx.store.categories(&hashes).unwrap_or_else(|x| {
+        error!("backEnd:get categories failed: {}", x);
+        HashMap::new()
+    });
+    torrents
+        .iter()
+        .map(|x| {
+            let hash = x.hash_string.as_deref().unwrap_or("");
+            let fx = ctx
+                .agent
+                .cachedInfo(hash, &ctx.store)
+                .map(|y| (y.uploadFX, y.downloadFX));
+            TorrentRow::new(x, categories.get(hash).cloned(), fx)
+        })
+        .collect()
+}
+
+fn backEnd(ctx: Arc<Context>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        let mut masks = 0u8;
+        let remote = ctx.agent.getUserInfo();
+        let local = ctx.agent.getStats();
+        let torrents = ctx.agent.getWorkingTorrent();
+        let (remote, local, torrents) = tokio::join!(remote, local, torrents);
+        let now = chrono::Local::now().timestamp();
+        if let Ok(x) = remote {
+            if let Ok(mut history) = ctx.history.write() {
+                history.pushRemote(now, &x);
             }
-            let mut work = Vec::new();
-            for x in now.iter() {
-                let future = agentSep2.performActionOnTorrent(
-                    (x.hash_string.as_ref().ok_or("broken torrent info")?).to_owned(),
-                    TorrentAction::Reannounce,
-                );
-                work.push(future);
+            if let Ok(mut lastRemote) = ctx.lastRemote.write() {
+                *lastRemote = Some(x)
+            } else {
+                error!("backEnd:get remote lock failed");
+            }
+            masks |= 2;
+        } else {
+            error!("backEnd:get U2 Info failed");
+        }
+
+        if let Ok(x) = local {
+            if let Ok(mut history) = ctx.history.write() {
+                history.pushSession(now, &x);
             }
-            let work = futures::future::join_all(work).await;
-            for i in work.into_iter() {
-                let _ = i?;
+            if let Ok(mut lastLocal) = ctx.lastLocal.write() {
+                *lastLocal = Some(x)
+            } else {
+                error!("backEnd:get local lock failed");
             }
-            info!("maintain done");
-            Ok(())
-        };
-        loop {
-            match handleOne().await {
-                Ok(_) => {}
-                Err(x) => {
-                    error!("maintain:{}", x);
+            masks |= 1;
+        } else {
+            error!("backEnd:get BT local info failed");
+        }
+
+        match torrents {
+            Ok(x) => {
+                let ctxRows = Arc::clone(&ctx);
+                // categories and U2 ids are read with rusqlite, off the runtime threads
+                match tokio::task::spawn_blocking(move || torrentRows(&ctxRows, &x.torrents)).await
+                {
+                    Ok(rows) => {
+                        if let Ok(mut torrents) = ctx.torrents.write() {
+                            if torrents.as_ref() != Some(&rows) {
+                                *torrents = Some(rows);
+                                ctx.torrentsGen.fetch_add(1, Ordering::SeqCst);
+                            }
+                        } else {
+                            error!("backEnd:get torrents lock failed");
+                        }
+                    }
+                    Err(x) => error!("backEnd:{}", x),
                 }
             }
-            sleep(Duration::from_secs(60 * 5)).await;
+            Err(_) => error!("backEnd:get BT torrents failed"),
         }
-    });
-    let _ = tokio::select! {
-        _ = promote => {}
-        _ = backEnd => {}
-        _ = frontEnd => {}
-        _ = keyboard => {}
-        _ = maintain => {}
-    };
-    disable_raw_mode()?;
-    Ok(())
+        if let Ok(mut mask) = ctx.mask.write() {
+            *mask = masks;
+        } else {
+            error!("backEnd:get masks lock failed");
+        }
+        Ok(())
+    })
+}
+
+fn maintain(ctx: Arc<Context>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        tasks::maintain::handleOne(&ctx.agent, &ctx.args, &ctx.removal, &ctx.store, &ctx.scope)
+            .await
+    })
+}
+
+fn expire(ctx: Arc<Context>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        if sitePaused(&ctx, "expire") {
+            return Ok(());
+        }
+        tasks::expire::handleOne(&ctx.agent, &ctx.args, &ctx.store, &ctx.scope).await
+    })
+}
+
+fn ucoin(ctx: Arc<Context>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        if sitePaused(&ctx, "ucoin") {
+            return Ok(());
+        }
+        tasks::ucoin::handleOne(&ctx.agent, &ctx.store, &ctx.ledger, &ctx.budget).await
+    })
+}
+
+/// keeps what backEnd saw last in the store
+fn snapshot(ctx: Arc<Context>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        let now = chrono::Local::now().timestamp();
+        let remote = ctx
+            .lastRemote
+            .read()
+            .map_err(|_| "snapshot:get remote lock failed")?
+            .clone();
+        if let Some(ref x) = remote {
+            ctx.store.recordUser(now, x)?;
+        }
+        let local = ctx
+            .lastLocal
+            .read()
+            .map_err(|_| "snapshot:get local lock failed")?
+            .clone();
+        if let Some(ref x) = local {
+            ctx.store.recordSession(now, x)?;
+        }
+        Ok(())
+    })
 }
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike};
+
+use crate::Result;
+
+/// a five field cron expression: minute hour day-of-month month day-of-week,
+/// each field being `*`, a number, a range `a-b`, a step `*/n` or `a-b/n`, or a list of those
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minute: Vec<bool>,
+    hour: Vec<bool>,
+    day: Vec<bool>,
+    month: Vec<bool>,
+    weekday: Vec<bool>,
+    /// whether day-of-month and day-of-week were both restricted, then either may match
+    either: bool,
+}
+
+fn parseField(x: &str, min: u32, max: u32) -> Result<(Vec<bool>, bool)> {
+    let mut ret = vec![false; max as usize + 1];
+    let mut restricted = false;
+    for part in x.split(',') {
+        let (range, step) = match part.find('/') {
+            Some(pos) => (&part[..pos], part[pos + 1..].parse::<u32>()?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(format!("cron:zero step in {}", x).into());
+        }
+        let (from, to) = if range == "*" {
+            (min, max)
+        } else {
+            restricted = true;
+            match range.find('-') {
+                Some(pos) => (range[..pos].parse()?, range[pos + 1..].parse()?),
+                None if part.contains('/') => (range.parse()?, max),
+                None => {
+                    let x = range.parse()?;
+                    (x, x)
+                }
+            }
+        };
+        if from < min || to > max || from > to {
+            return Err(format!("cron:{} out of {}-{}", part, min, max).into());
+        }
+        for i in (from..=to).step_by(step as usize) {
+            ret[i as usize] = true;
+        }
+    }
+    Ok((ret, restricted))
+}
+
+impl Cron {
+    pub fn parse(x: &str) -> Result<Cron> {
+        let fields: Vec<&str> = x.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("cron:expect 5 fields in {}", x).into());
+        }
+        let (minute, _) = parseField(fields[0], 0, 59)?;
+        let (hour, _) = parseField(fields[1], 0, 23)?;
+        let (day, dayRestricted) = parseField(fields[2], 1, 31)?;
+        let (month, _) = parseField(fields[3], 1, 12)?;
+        // 7 is sunday as well
+        let (mut weekday, weekdayRestricted) = parseField(fields[4], 0, 7)?;
+        if weekday[7] {
+            weekday[0] = true;
+        }
+        Ok(Cron {
+            minute,
+            hour,
+            day,
+            month,
+            weekday,
+            either: dayRestricted && weekdayRestricted,
+        })
+    }
+
+    fn dayMatches(&self, x: &DateTime<Local>) -> bool {
+        let day = self.day[x.day() as usize];
+        let weekday = self.weekday[x.weekday().num_days_from_sunday() as usize];
+        self.month[x.month() as usize]
+            && if self.either {
+                day || weekday
+            } else {
+                day && weekday
+            }
+    }
+
+    pub fn matches(&self, time: i64) -> bool {
+        let x = Local.timestamp(time, 0);
+        self.minute[x.minute() as usize] && self.hour[x.hour() as usize] && self.dayMatches(&x)
+    }
+
+    /// the first matching minute after `time`, in local time
+    pub fn next(&self, time: i64) -> Option<i64> {
+        let mut x = time.div_euclid(60) * 60 + 60;
+        // a little over four years covers every february 29th
+        while x < time + (4 * 366 + 1) * 86400 {
+            let t = Local.timestamp(x, 0);
+            if !self.dayMatches(&t) {
+                x += ((23 - t.hour()) * 3600 + (60 - t.minute()) * 60) as i64;
+            } else if !self.hour[t.hour() as usize] {
+                x += ((60 - t.minute()) * 60) as i64;
+            } else if !self.minute[t.minute() as usize] {
+                x += 60;
+            } else {
+                return Some(x);
+            }
+        }
+        None
+    }
+}
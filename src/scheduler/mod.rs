@@ -0,0 +1,263 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, RwLock};
+
+use chrono::Local;
+use futures::future::BoxFuture;
+use log::{error, info};
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+
+use crate::Result;
+
+pub mod cron;
+
+use self::cron::Cron;
+
+/// `[jobs.<name>]` in args.toml, a missing key keeps the job's default
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct JobConfig {
+    /// an interval like "90s", "5m", "2h" or a cron expression
+    pub schedule: Option<String>,
+    /// seconds, the most a run is delayed at random
+    pub jitter: Option<u64>,
+    /// seconds a run may take before it is cancelled
+    pub timeout: Option<u64>,
+    /// seconds until a failed run is retried instead of waiting for the schedule
+    pub retry: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// the time between the end of a run and the start of the next, the first run starts at once
+    Every(Duration),
+    Cron(Cron),
+}
+
+impl Schedule {
+    pub fn parse(x: &str) -> Result<Schedule> {
+        let x = x.trim();
+        if x.split_whitespace().count() > 1 {
+            return Ok(Schedule::Cron(Cron::parse(x)?));
+        }
+        let (num, unit) = x.split_at(x.find(|c: char| !c.is_ascii_digit()).unwrap_or(x.len()));
+        let num: u64 = num.parse()?;
+        let secs = match unit {
+            "" | "s" => num,
+            "m" => num * 60,
+            "h" => num * 3600,
+            "d" => num * 86400,
+            _ => return Err(format!("schedule:unknown unit in {}", x).into()),
+        };
+        Ok(Schedule::Every(Duration::from_secs(secs)))
+    }
+
+    /// unix time of the next run, `last` is when the last run ended
+    pub fn next(&self, now: i64, last: Option<i64>) -> Option<i64> {
+        match self {
+            Schedule::Every(x) => Some(match last {
+                Some(last) => last + x.as_secs() as i64,
+                None => now,
+            }),
+            Schedule::Cron(x) => x.next(now),
+        }
+    }
+}
+
+pub struct Job {
+    pub name: String,
+    pub schedule: Schedule,
+    pub jitter: Duration,
+    pub timeout: Option<Duration>,
+    pub retry: Option<Duration>,
+}
+
+impl Job {
+    /// `schedule` is the default, overridden by `config`
+    pub fn new(name: &str, schedule: &str, config: Option<&JobConfig>) -> Result<Job> {
+        let config = config.cloned().unwrap_or_default();
+        let schedule = config.schedule.as_deref().unwrap_or(schedule);
+        Ok(Job {
+            name: name.to_string(),
+            schedule: Schedule::parse(schedule).map_err(|x| format!("job {}:{}", name, x))?,
+            jitter: Duration::from_secs(config.jitter.unwrap_or(0)),
+            timeout: config.timeout.map(Duration::from_secs),
+            retry: config.retry.map(Duration::from_secs),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobState {
+    Waiting,
+    Running,
+    Done,
+    Failed(String),
+    TimedOut,
+    Panicked,
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobState::Waiting => write!(f, "waiting"),
+            JobState::Running => write!(f, "running"),
+            JobState::Done => write!(f, "ok"),
+            JobState::Failed(x) => write!(f, "failed:{}", x),
+            JobState::TimedOut => write!(f, "timed out"),
+            JobState::Panicked => write!(f, "panicked"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobStatus {
+    pub name: String,
+    pub state: JobState,
+    /// unix time the last run started
+    pub lastRun: Option<i64>,
+    pub nextRun: Option<i64>,
+    pub runs: u64,
+    pub failures: u64,
+}
+
+pub type JobFn<T> = fn(Arc<T>) -> BoxFuture<'static, Result<()>>;
+
+/// runs every job in its own loop, one run at a time, so a job never overlaps itself
+/// and a failed, stuck or panicking run only costs that run
+pub struct Scheduler<T> {
+    ctx: Arc<T>,
+    status: Arc<RwLock<Vec<JobStatus>>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+fn jitter(max: Duration) -> Duration {
+    let max = max.as_millis() as u64;
+    if max == 0 {
+        return Duration::from_millis(0);
+    }
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(random % (max + 1))
+}
+
+impl<T: Send + Sync + 'static> Scheduler<T> {
+    pub fn new(ctx: Arc<T>) -> Scheduler<T> {
+        Scheduler {
+            ctx,
+            status: Arc::new(RwLock::new(Vec::new())),
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn status(&self) -> Arc<RwLock<Vec<JobStatus>>> {
+        Arc::clone(&self.status)
+    }
+
+    pub fn add(&mut self, job: Job, run: JobFn<T>) {
+        let idx = match self.status.write() {
+            Ok(mut status) => {
+                status.push(JobStatus {
+                    name: job.name.to_owned(),
+                    state: JobState::Waiting,
+                    lastRun: None,
+                    nextRun: None,
+                    runs: 0,
+                    failures: 0,
+                });
+                status.len() - 1
+            }
+            Err(_) => {
+                error!("scheduler:status lock failed, {} not added", job.name);
+                return;
+            }
+        };
+        let ctx = Arc::clone(&self.ctx);
+        let status = Arc::clone(&self.status);
+        self.handles.push(tokio::task::spawn(async move {
+            Self::runJob(job, run, ctx, status, idx).await
+        }));
+    }
+
+    fn update(status: &RwLock<Vec<JobStatus>>, idx: usize, f: impl FnOnce(&mut JobStatus)) {
+        if let Ok(mut status) = status.write() {
+            if let Some(x) = status.get_mut(idx) {
+                f(x);
+            }
+        }
+    }
+
+    async fn runJob(
+        job: Job,
+        run: JobFn<T>,
+        ctx: Arc<T>,
+        status: Arc<RwLock<Vec<JobStatus>>>,
+        idx: usize,
+    ) {
+        let mut last = None;
+        let mut failed = false;
+        loop {
+            let now = Local::now().timestamp();
+            let next = match (failed, job.retry) {
+                (true, Some(retry)) => Some(now + retry.as_secs() as i64),
+                _ => job.schedule.next(now, last),
+            };
+            let next = match next {
+                Some(x) => x,
+                None => {
+                    error!("scheduler:{} will never run again", job.name);
+                    return;
+                }
+            };
+            Self::update(&status, idx, |x| x.nextRun = Some(next));
+            let wait = Duration::from_secs((next - now).max(0) as u64) + jitter(job.jitter);
+            sleep(wait).await;
+
+            let start = Local::now().timestamp();
+            Self::update(&status, idx, |x| {
+                x.state = JobState::Running;
+                x.lastRun = Some(start);
+                x.nextRun = None;
+            });
+            let mut handle = tokio::task::spawn(run(Arc::clone(&ctx)));
+            let res = match job.timeout {
+                Some(limit) => timeout(limit, &mut handle).await,
+                None => Ok((&mut handle).await),
+            };
+            let state = match res {
+                Ok(Ok(Ok(_))) => JobState::Done,
+                Ok(Ok(Err(x))) => {
+                    error!("{}:{}", job.name, x);
+                    JobState::Failed(x.to_string())
+                }
+                Ok(Err(x)) => {
+                    error!("scheduler:{} panicked, {}", job.name, x);
+                    JobState::Panicked
+                }
+                Err(_) => {
+                    handle.abort();
+                    error!("scheduler:{} timed out", job.name);
+                    JobState::TimedOut
+                }
+            };
+            failed = state != JobState::Done;
+            if failed {
+                info!("scheduler:{} restarts", job.name);
+            }
+            last = Some(Local::now().timestamp());
+            Self::update(&status, idx, |x| {
+                x.runs += 1;
+                if failed {
+                    x.failures += 1;
+                }
+                x.state = state;
+            });
+        }
+    }
+
+    /// waits for every job, which only happens once none can run again
+    pub async fn run(self) {
+        futures::future::join_all(self.handles).await;
+    }
+}
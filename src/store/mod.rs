@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::torrentLib::response::SessionStats;
+use crate::u2client::types::{UCoinEntry, UserInfo};
+use crate::Result;
+
+/// a torrent the tool added itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddedTorrent {
+    pub time: i64,
+    pub uid: String,
+    pub hash: String,
+    pub title: String,
+    pub category: String,
+    pub rule: String,
+    /// debug form of the `Magic` cast before adding
+    pub magic: Option<String>,
+    /// UCoin
+    pub cost: Option<f64>,
+}
+
+/// UCoin spent on a magic, kept apart from `added` so a failed add leaves a trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagicCast {
+    pub time: i64,
+    pub uid: String,
+    pub hash: String,
+    pub title: String,
+    /// debug form of the `Magic`
+    pub magic: String,
+    /// UCoin
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovedTorrent {
+    pub time: i64,
+    pub hash: String,
+    pub name: String,
+    pub size: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSample {
+    pub time: i64,
+    pub downloadSpeed: i64,
+    pub uploadSpeed: i64,
+    pub activeTorrentCount: i64,
+    pub torrentCount: i64,
+    /// cumulative bytes
+    pub uploaded: i64,
+    pub downloaded: i64,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS added (
+    time INTEGER NOT NULL,
+    uid TEXT NOT NULL,
+    hash TEXT NOT NULL PRIMARY KEY,
+    title TEXT NOT NULL,
+    category TEXT NOT NULL,
+    rule TEXT NOT NULL,
+    magic TEXT,
+    cost REAL
+);
+CREATE TABLE IF NOT EXISTS magic (
+    time INTEGER NOT NULL,
+    uid TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    title TEXT NOT NULL,
+    magic TEXT NOT NULL,
+    cost REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS removed (
+    time INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    reason TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS user (
+    time INTEGER NOT NULL,
+    username TEXT NOT NULL,
+    download TEXT NOT NULL,
+    upload TEXT NOT NULL,
+    shareRate TEXT NOT NULL,
+    actualDownload TEXT NOT NULL,
+    actualUpload TEXT NOT NULL,
+    coin TEXT NOT NULL,
+    downloadTime TEXT NOT NULL,
+    uploadTime TEXT NOT NULL,
+    timeRate TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS session (
+    time INTEGER NOT NULL,
+    downloadSpeed INTEGER NOT NULL,
+    uploadSpeed INTEGER NOT NULL,
+    activeTorrentCount INTEGER NOT NULL,
+    torrentCount INTEGER NOT NULL,
+    uploaded INTEGER NOT NULL,
+    downloaded INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS ucoin (
+    time INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    reason TEXT NOT NULL,
+    torrent TEXT,
+    seq INTEGER NOT NULL,
+    PRIMARY KEY (time, amount, reason, seq)
+);
+CREATE TABLE IF NOT EXISTS upload (
+    time INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    uploaded INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS uploadHash ON upload (hash, time);
+CREATE INDEX IF NOT EXISTS magicTime ON magic (time);
+CREATE INDEX IF NOT EXISTS removedTime ON removed (time);
+CREATE INDEX IF NOT EXISTS userTime ON user (time);
+CREATE INDEX IF NOT EXISTS sessionTime ON session (time);
+";
+
+/// what the automation did and saw, kept across restarts in a SQLite file
+pub struct Store {
+    conn: Mutex<Connection>,
+}
+
+impl Store {
+    /// `:memory:` gives a store that lives as long as the process
+    pub fn open(path: &str) -> Result<Store> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        Ok(self.conn.lock().map_err(|_| "store:lock failed")?)
+    }
+
+    pub fn recordAdded(&self, x: &AddedTorrent) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO added (time, uid, hash, title, category, rule, magic, cost)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![x.time, x.uid, x.hash, x.title, x.category, x.rule, x.magic, x.cost],
+        )?;
+        Ok(())
+    }
+
+    pub fn added(&self, hash: &str) -> Result<Option<AddedTorrent>> {
+        Ok(self
+            .conn()?
+            .query_row(
+                "SELECT time, uid, hash, title, category, rule, magic, cost
+                 FROM added WHERE hash = ?1",
+                params![hash],
+                Store::addedRow,
+            )
+            .optional()?)
+    }
+
+    pub fn addedSince(&self, since: i64) -> Result<Vec<AddedTorrent>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT time, uid, hash, title, category, rule, magic, cost
+             FROM added WHERE time >= ?1 ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![since], Store::addedRow)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// the category of each of `hashes` the tool added, by hash
+    pub fn categories(&self, hashes: &[&str]) -> Result<HashMap<String, String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT category FROM added WHERE hash = ?1")?;
+        let mut res = HashMap::new();
+        for hash in hashes {
+            let category: Option<String> =
+                stmt.query_row(params![hash], |row| row.get(0)).optional()?;
+            if let Some(x) = category {
+                res.insert(hash.to_string(), x);
+            }
+        }
+        Ok(res)
+    }
+
+    fn addedRow(row: &rusqlite::Row) -> rusqlite::Result<AddedTorrent> {
+        Ok(AddedTorrent {
+            time: row.get(0)?,
+            uid: row.get(1)?,
+            hash: row.get(2)?,
+            title: row.get(3)?,
+            category: row.get(4)?,
+            rule: row.get(5)?,
+            magic: row.get(6)?,
+            cost: row.get(7)?,
+        })
+    }
+
+    pub fn recordMagic(&self, x: &MagicCast) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO magic (time, uid, hash, title, magic, cost) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![x.time, x.uid, x.hash, x.title, x.magic, x.cost],
+        )?;
+        Ok(())
+    }
+
+    pub fn magicSince(&self, since: i64) -> Result<Vec<MagicCast>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT time, uid, hash, title, magic, cost FROM magic WHERE time >= ?1 ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(MagicCast {
+                time: row.get(0)?,
+                uid: row.get(1)?,
+                hash: row.get(2)?,
+                title: row.get(3)?,
+                magic: row.get(4)?,
+                cost: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn recordRemoved(&self, x: &RemovedTorrent) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO removed (time, hash, name, size, reason) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![x.time, x.hash, x.name, x.size, x.reason],
+        )?;
+        Ok(())
+    }
+
+    pub fn removedSince(&self, since: i64) -> Result<Vec<RemovedTorrent>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT time, hash, name, size, reason FROM removed WHERE time >= ?1 ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(RemovedTorrent {
+                time: row.get(0)?,
+                hash: row.get(1)?,
+                name: row.get(2)?,
+                size: row.get(3)?,
+                reason: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn recordUser(&self, time: i64, x: &UserInfo) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO user (time, username, download, upload, shareRate, actualDownload,
+                 actualUpload, coin, downloadTime, uploadTime, timeRate)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                time,
+                x.username,
+                x.download,
+                x.upload,
+                x.shareRate,
+                x.actualDownload,
+                x.actualUpload,
+                x.coin,
+                x.downloadTime,
+                x.uploadTime,
+                x.timeRate
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn userSince(&self, since: i64) -> Result<Vec<(i64, UserInfo)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT time, username, download, upload, shareRate, actualDownload, actualUpload,
+                 coin, downloadTime, uploadTime, timeRate
+             FROM user WHERE time >= ?1 ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok((
+                row.get(0)?,
+                UserInfo {
+                    username: row.get(1)?,
+                    download: row.get(2)?,
+                    upload: row.get(3)?,
+                    shareRate: row.get(4)?,
+                    actualDownload: row.get(5)?,
+                    actualUpload: row.get(6)?,
+                    coin: row.get(7)?,
+                    downloadTime: row.get(8)?,
+                    uploadTime: row.get(9)?,
+                    timeRate: row.get(10)?,
+                },
+            ))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn recordSession(&self, time: i64, x: &SessionStats) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO session (time, downloadSpeed, uploadSpeed, activeTorrentCount,
+                 torrentCount, uploaded, downloaded)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                time,
+                x.downloadSpeed as i64,
+                x.uploadSpeed as i64,
+                x.activeTorrentCount as i64,
+                x.torrentCount as i64,
+                x.cumulative_stats.uploadedBytes as i64,
+                x.cumulative_stats.downloadedBytes as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn sessionSince(&self, since: i64) -> Result<Vec<SessionSample>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT time, downloadSpeed, uploadSpeed, activeTorrentCount, torrentCount,
+                 uploaded, downloaded
+             FROM session WHERE time >= ?1 ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(SessionSample {
+                time: row.get(0)?,
+                downloadSpeed: row.get(1)?,
+                uploadSpeed: row.get(2)?,
+                activeTorrentCount: row.get(3)?,
+                torrentCount: row.get(4)?,
+                uploaded: row.get(5)?,
+                downloaded: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// the rows of the UCoin log not stored yet, returns how many were new
+    ///
+    /// rows alike in time, amount and reason are told apart by their count in `entries`,
+    /// so two equal incomes within a second are both kept
+    pub fn recordUCoin(&self, entries: &[UCoinEntry]) -> Result<usize> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT OR IGNORE INTO ucoin (time, amount, reason, torrent, seq)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        let mut seen: HashMap<(i64, u64, &str), i64> = HashMap::new();
+        let mut cnt = 0;
+        for x in entries.iter() {
+            let seq = seen
+                .entry((x.time, x.amount.to_bits(), x.reason.as_str()))
+                .or_insert(0);
+            cnt += stmt.execute(params![x.time, x.amount, x.reason, x.torrent, *seq])?;
+            *seq += 1;
+        }
+        Ok(cnt)
+    }
+
+    pub fn ucoinSince(&self, since: i64) -> Result<Vec<UCoinEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT time, amount, reason, torrent FROM ucoin WHERE time >= ?1 ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(UCoinEntry {
+                time: row.get(0)?,
+                amount: row.get(1)?,
+                reason: row.get(2)?,
+                torrent: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// the time of the newest row of the UCoin log stored
+    pub fn ucoinLatest(&self) -> Result<Option<i64>> {
+        Ok(self
+            .conn()?
+            .query_row("SELECT MAX(time) FROM ucoin", params![], |row| row.get(0))?)
+    }
+
+    /// `uploadedEver` of the torrents by hash at `time`, keeping `keep` seconds of samples
+    /// plus one older as the baseline and forgetting torrents not sampled now
+    pub fn recordUploads(&self, time: i64, samples: &[(&str, i64)], keep: i64) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare_cached("INSERT INTO upload (time, hash, uploaded) VALUES (?1, ?2, ?3)")?;
+            for (hash, uploaded) in samples.iter() {
+                stmt.execute(params![time, hash, uploaded])?;
+            }
+        }
+        tx.execute(
+            "DELETE FROM upload WHERE time < ?1
+                 AND hash NOT IN (SELECT hash FROM upload WHERE time = ?1)",
+            params![time],
+        )?;
+        tx.execute(
+            "DELETE FROM upload WHERE time < (SELECT MAX(time) FROM upload AS x
+                 WHERE x.hash = upload.hash AND x.time <= ?1)",
+            params![time - keep],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// (time, hash, uploaded) after `since` and the last one before it of each torrent
+    pub fn uploadsSince(&self, since: i64) -> Result<Vec<(i64, String, i64)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT time, hash, uploaded FROM upload
+             WHERE time >= COALESCE((SELECT MAX(time) FROM upload AS x
+                 WHERE x.hash = upload.hash AND x.time <= ?1), ?1)
+             ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+
+use crate::u2client::types::Config;
+
+/// the rolling window of `magicBudget`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+}
+
+impl BudgetPeriod {
+    pub fn seconds(&self) -> i64 {
+        match self {
+            BudgetPeriod::Daily => 86400,
+            BudgetPeriod::Weekly => 7 * 86400,
+        }
+    }
+}
+
+/// caps the UCoin spent on magic within a rolling window
+pub struct MagicBudget {
+    limit: Option<f64>,
+    window: i64,
+    /// reservations made since `syncedAt`
+    spent: VecDeque<(i64, f64)>,
+    /// spending read from the UCoin log
+    synced: Vec<(i64, f64)>,
+    syncedAt: i64,
+    balance: Option<f64>,
+}
+
+impl MagicBudget {
+    pub fn new(args: &Config) -> MagicBudget {
+        MagicBudget {
+            limit: args.magicBudget,
+            window: args
+                .magicBudgetPeriod
+                .unwrap_or(BudgetPeriod::Daily)
+                .seconds(),
+            spent: VecDeque::new(),
+            synced: Vec::new(),
+            syncedAt: 0,
+            balance: None,
+        }
+    }
+
+    /// the UCoin we own right now, as read from the user page
+    pub fn setBalance(&mut self, balance: f64) {
+        self.balance = Some(balance);
+    }
+
+    pub fn spent(&mut self, now: i64) -> f64 {
+        while let Some(x) = self.spent.front() {
+            if x.0 + self.window <= now {
+                self.spent.pop_front();
+            } else {
+                break;
+            }
+        }
+        let from = now - self.window;
+        self.spent.iter().map(|x| x.1).sum::<f64>()
+            + self
+                .synced
+                .iter()
+                .filter(|x| x.0 > from)
+                .map(|x| x.1)
+                .sum::<f64>()
+    }
+
+    /// replace our own bookkeeping with the UCoin log, which covers everything until `now`
+    pub fn sync(&mut self, now: i64, history: Vec<(i64, f64)>) {
+        self.synced = history;
+        self.syncedAt = now;
+        while let Some(x) = self.spent.front() {
+            if x.0 < self.syncedAt {
+                self.spent.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn remaining(&mut self, now: i64) -> Option<f64> {
+        let limit = self.limit?;
+        Some((limit - self.spent(now)).max(0.0))
+    }
+
+    /// book `cost` if both the budget and the balance allow it
+    pub fn reserve(&mut self, now: i64, cost: f64) -> bool {
+        if let Some(x) = self.remaining(now) {
+            if cost > x {
+                return false;
+            }
+        }
+        if let Some(x) = self.balance {
+            if cost > x {
+                return false;
+            }
+            self.balance = Some(x - cost);
+        }
+        self.spent.push_back((now, cost));
+        true
+    }
+
+    /// give back a reservation whose magic was not cast after all
+    pub fn refund(&mut self, now: i64, cost: f64) {
+        if let Some(pos) = self
+            .spent
+            .iter()
+            .rposition(|x| x.0 == now && (x.1 - cost).abs() < f64::EPSILON)
+        {
+            self.spent.remove(pos);
+            if let Some(x) = self.balance {
+                self.balance = Some(x + cost);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::Local;
+use log::{info, warn};
+use serde::Deserialize;
+
+use crate::store::{RemovedTorrent, Store};
+use crate::tasks::scope::Scope;
+use crate::torrentLib::request::TorrentAction;
+use crate::u2client::client::U2client;
+use crate::u2client::types::Config;
+use crate::Result;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExpireAction {
+    Pause,
+    Remove,
+}
+
+impl std::fmt::Display for ExpireAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpireAction::Pause => write!(f, "pause"),
+            ExpireAction::Remove => write!(f, "remove"),
+        }
+    }
+}
+
+/// whether a download with `eta` seconds left, plus `margin`, runs past `end`;
+/// with no eta, as for a stalled torrent, only once the window is about to close
+pub fn overruns(now: i64, eta: Option<i64>, margin: i64, end: i64) -> bool {
+    if end == i64::MAX {
+        return false;
+    }
+    let finish = match eta {
+        Some(x) if x >= 0 => now + x,
+        _ => now,
+    };
+    finish.saturating_add(margin) > end
+}
+
+/// pause or drop unfinished downloads which can not finish before their free window closes
+pub async fn handleOne(
+    agent: &U2client,
+    args: &Config,
+    store: &Store,
+    scope: &Scope,
+) -> Result<()> {
+    let action = match args.expireAction {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let margin = args.expireMargin.unwrap_or(30) * 60;
+    let now = Local::now().timestamp();
+
+    let site = agent.getSitePromotion().await?;
+    let siteEnd = match site {
+        Some(ref x) if x.downloadFX == 0.0 => Some(x.expire.unwrap_or(i64::MAX)),
+        _ => None,
+    };
+
+    let working = scope.filter(agent.getWorkingTorrent().await?.torrents, store)?;
+    for i in working.into_iter() {
+        if i.percent_done.unwrap_or(0.0) >= 1.0 || i.status == Some(0) {
+            continue;
+        }
+        let hash = i.hash_string.ok_or("expire:bad torrent hash")?;
+        let uid = match agent.uidOf(&hash, store) {
+            Ok(Some(x)) => x,
+            Ok(None) => continue,
+            Err(x) => {
+                warn!("expire:no U2 id of {}, {}", hash, x);
+                continue;
+            }
+        };
+        let info = match agent.getTorrentInfo(&uid).await {
+            Ok(x) => x,
+            Err(x) => {
+                warn!("expire:no details of {}, {}", uid, x);
+                continue;
+            }
+        };
+        let torrentEnd = if info.downloadFX == 0.0 {
+            Some(info.promotionExpire.unwrap_or(i64::MAX))
+        } else {
+            None
+        };
+        let end = match (torrentEnd, siteEnd) {
+            (Some(a), Some(b)) => a.max(b),
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => continue,
+        };
+        if !overruns(now, i.eta, margin, end) {
+            continue;
+        }
+        let name = i.name.unwrap_or_default();
+        if args.dryRun.unwrap_or(false) {
+            info!(
+                "expire:dry run, would {} {}, free window closes at {}",
+                action, name, end
+            );
+        } else if action == ExpireAction::Remove {
+            info!("expire:remove {}, free window closes at {}", name, end);
+            if let Err(x) = agent.removeTorrent(hash.to_owned(), true).await {
+                warn!("expire:remove {} failed, {}", name, x);
+                continue;
+            }
+            store.recordRemoved(&RemovedTorrent {
+                time: now,
+                hash,
+                name,
+                size: i.total_size.unwrap_or(0),
+                reason: format!("free window closes at {}", end),
+            })?;
+        } else {
+            info!("expire:pause {}, free window closes at {}", name, end);
+            if let Err(x) = agent
+                .performActionOnTorrent(hash, TorrentAction::Stop)
+                .await
+            {
+                warn!("expire:pause {} failed, {}", name, x);
+            }
+        }
+    }
+    info!("expire:done");
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Local;
+use log::{info, warn};
+
+use crate::store::{RemovedTorrent, Store};
+use crate::tasks::removal::Removal;
+use crate::tasks::scope::{self, Scope};
+use crate::tasks::GIB;
+use crate::torrentLib::request::TorrentAction;
+use crate::torrentLib::response::Torrent;
+use crate::u2client::client::U2client;
+use crate::u2client::types::{Config, SeedingRecord};
+use crate::Result;
+
+pub async fn handleOne(
+    agent: &U2client,
+    args: &Config,
+    removal: &Removal,
+    store: &Store,
+    scope: &Scope,
+) -> Result<()> {
+    let now = scope.filter(agent.getWorkingTorrent().await?.torrents, store)?;
+    let time = Local::now().timestamp();
+    removal.record(store, time, &now)?;
+    let mut tot = 0f32;
+    for i in now.iter() {
+        let sb = i.total_size.unwrap_or(0) as f32 / GIB;
+        tot += sb;
+    }
+    let mut reason = None;
+    if tot > args.maxSize {
+        reason = Some(format!("{} GiB over maxSize {}", tot, args.maxSize));
+    }
+    let mut excess = ((tot - args.maxSize) * GIB) as i64;
+    if let Some(low) = args.lowWatermark {
+        let free = agent.getDiskFree().await? as f32 / GIB;
+        if free < low {
+            info!(
+                "maintain:{} GiB free, below the low watermark {}",
+                free, low
+            );
+            let high = args.highWatermark.unwrap_or(low).max(low);
+            reason = Some(format!("{} GiB free under lowWatermark {}", free, low));
+            excess = excess.max(((high - free) * GIB) as i64);
+        }
+    }
+    if let Some(reason) = reason {
+        let protected = obligations(agent, args, store, &now).await?;
+        let V = removal.pick(store, now.clone(), &protected, excess, time)?;
+        let mut all = Vec::new();
+        let mut removed = Vec::new();
+        for i in V.into_iter() {
+            let name = i.name.ok_or("handleOne:broken name")?;
+            let size = i.total_size.ok_or("handleOne:broken size")?;
+            let hash = i.hash_string.ok_or("handleOne:broken hash")?;
+            if args.dryRun.unwrap_or(false) {
+                info!(
+                    "maintain:dry run, would remove {}, {} GiB",
+                    name,
+                    size as f32 / GIB
+                );
+                continue;
+            }
+            info!("maintain:remove {}, {} GiB", name, size as f32 / GIB);
+            all.push(agent.removeTorrent(hash.to_owned(), true));
+            removed.push(RemovedTorrent {
+                time,
+                hash,
+                name,
+                size,
+                reason: reason.to_owned(),
+            });
+        }
+        let res = futures::future::join_all(all).await;
+        // the removals that went through are recorded even if others failed
+        for (x, i) in res.into_iter().zip(removed.iter()) {
+            match x {
+                Ok(_) => store.recordRemoved(i)?,
+                Err(e) => warn!("maintain:remove {} failed, {}", i.name, e),
+            }
+        }
+    }
+    let mut work = Vec::new();
+    let mut hashes = Vec::new();
+    for x in now.iter() {
+        let hash = (x.hash_string.as_ref().ok_or("broken torrent info")?).to_owned();
+        work.push(agent.performActionOnTorrent(hash.to_owned(), TorrentAction::Reannounce));
+        hashes.push(hash);
+    }
+    let work = futures::future::join_all(work).await;
+    for (x, hash) in work.into_iter().zip(hashes.iter()) {
+        if let Err(e) = x {
+            warn!("maintain:reannounce {} failed, {}", hash, e);
+        }
+    }
+    info!("maintain done");
+    Ok(())
+}
+
+/// hashes of torrents in `working` whose seeding requirement on the site is not met yet,
+/// or may not be as their U2 id is unknown
+async fn obligations(
+    agent: &U2client,
+    args: &Config,
+    store: &Store,
+    working: &[Torrent],
+) -> Result<HashSet<String>> {
+    if args.minSeedTime.is_none() && args.minSeedRatio.is_none() {
+        return Ok(HashSet::new());
+    }
+    let (uids, mut ret) = resolve(agent, store, working);
+    ret.extend(unmet(args, &agent.getSeedingRecords().await?, &uids));
+    Ok(ret)
+}
+
+/// U2 id to hash of the torrents in `working`, and the hashes of the U2 torrents whose id
+/// is unknown, which are kept as they may still owe seeding
+pub fn resolve(
+    agent: &U2client,
+    store: &Store,
+    working: &[Torrent],
+) -> (HashMap<String, String>, HashSet<String>) {
+    let mut uids = HashMap::new();
+    let mut unknown = HashSet::new();
+    for x in working.iter() {
+        let hash = match x.hash_string.as_ref() {
+            Some(x) => x,
+            None => continue,
+        };
+        match agent.uidOf(hash, store) {
+            Ok(Some(uid)) => {
+                uids.insert(uid, hash.to_owned());
+            }
+            Ok(None) if !scope::announcesToU2(x) => {}
+            Ok(None) => {
+                info!("maintain:keep {}, its U2 id is unknown", hash);
+                unknown.insert(hash.to_owned());
+            }
+            Err(e) => {
+                warn!("maintain:keep {}, no U2 id, {}", hash, e);
+                unknown.insert(hash.to_owned());
+            }
+        }
+    }
+    (uids, unknown)
+}
+
+/// hashes of the records in `uids`, U2 id to hash, that are not seeded long enough
+pub fn unmet(
+    args: &Config,
+    records: &[SeedingRecord],
+    uids: &HashMap<String, String>,
+) -> HashSet<String> {
+    let minSeedTime = args.minSeedTime.map(|x| (x * 3600.0) as i64);
+    let mut ret = HashSet::new();
+    for i in records.iter() {
+        let met = minSeedTime.iter().any(|x| i.seedTime >= *x)
+            || args.minSeedRatio.iter().any(|x| i.ratio >= *x);
+        if let (false, Some(hash)) = (met, uids.get(&i.uid)) {
+            info!(
+                "maintain:keep {}, seeded {}s with ratio {}",
+                i.uid, i.seedTime, i.ratio
+            );
+            ret.insert(hash.to_owned());
+        }
+    }
+    ret
+}
@@ -0,0 +1,12 @@
+pub mod budget;
+pub mod expire;
+pub mod maintain;
+pub mod promote;
+pub mod removal;
+pub mod rules;
+pub mod scope;
+pub mod score;
+pub mod ucoin;
+
+/// bytes in a GiB, the unit of every size limit
+pub const GIB: f32 = 1024.0 * 1024.0 * 1024.0;
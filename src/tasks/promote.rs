@@ -0,0 +1,469 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+
+use chrono::Local;
+use log::{error, info, warn};
+
+use crate::store::{AddedTorrent, MagicCast, Store};
+use crate::tasks::budget::MagicBudget;
+use crate::tasks::rules::{magicHours, Decision, RuleEngine};
+use crate::tasks::score::{Candidate, Scorer};
+use crate::tasks::GIB;
+use crate::u2client::client::U2client;
+use crate::u2client::types::{
+    Config, Magic, MagicRequest, MagicTarget, Promotion, RssInfo, SearchQuery, SortBy,
+};
+use crate::Result;
+
+/// a torrent to add, the rule that picked it, whether paused, and the magic to cast first
+type Job<'a> = (&'a RssInfo, String, bool, Option<MagicRequest>);
+/// the rule torrents added from the dashboard are recorded under
+pub const BY_HAND: &str = "by hand";
+
+/// a job, or why there is none
+type Decided<'a> = std::result::Result<Job<'a>, Verdict>;
+/// the UCoin booked for the magic of a job, or why the budget does not allow it
+type Booked = std::result::Result<Option<f64>, String>;
+
+/// what a promote cycle made of a feed item
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// already in the client
+    Present,
+    Skipped(String),
+    /// the magic cast with its cost in UCoin, `dryRun` if it only would have been
+    Added {
+        rule: String,
+        magic: Option<(String, f64)>,
+        dryRun: bool,
+    },
+    Failed(String),
+}
+
+impl Verdict {
+    pub fn describe(&self) -> String {
+        match self {
+            Verdict::Present => "in client".to_string(),
+            Verdict::Skipped(why) => format!("skip, {}", why),
+            Verdict::Added {
+                rule,
+                magic,
+                dryRun,
+            } => {
+                let mut ret = if *dryRun {
+                    format!("would add by {}", rule)
+                } else {
+                    format!("added by {}", rule)
+                };
+                if let Some((magic, cost)) = magic {
+                    ret.push_str(&format!(", {} for {:.0} UCoin", magic, cost));
+                }
+                ret
+            }
+            Verdict::Failed(why) => format!("failed, {}", why),
+        }
+    }
+}
+
+/// a feed item as the last promote cycle saw it
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    pub item: RssInfo,
+    /// only torrents the rules would add are scored
+    pub score: Option<f32>,
+    pub verdict: Verdict,
+}
+
+pub async fn handleOne(
+    agent: &U2client,
+    args: &Config,
+    rules: &RuleEngine,
+    budget: &Mutex<MagicBudget>,
+    store: &Store,
+    last: &RwLock<Option<Vec<FeedEntry>>>,
+) -> Result<()> {
+    let working = agent.getWorkingTorrent().await?.torrents;
+    let pending = working
+        .iter()
+        .map(|x| x.left_until_done.unwrap_or(0))
+        .sum::<i64>() as f32
+        / GIB;
+    let mut torrentList: HashSet<String> = HashSet::new();
+    for x in working.into_iter() {
+        let x = x.hash_string.ok_or("handleOne:bad torrent hash")?;
+        torrentList.insert(x);
+    }
+    // torrents already in the client need no fresh details
+    let mut feed = agent.getDownloadList(&torrentList).await?;
+
+    for page in 0..args.searchPages.unwrap_or(0) {
+        let query = SearchQuery {
+            promotion: Some(Promotion::Free),
+            minSeeder: Some(1),
+            sort: Some(SortBy::Time),
+            page,
+            ..SearchQuery::default()
+        };
+        // the feed is worth handling without the rest of the search
+        let res = match agent.search_torrents(&query, &torrentList).await {
+            Ok(x) => x,
+            Err(x) => {
+                warn!("promote:search page {} failed, {}", page, x);
+                break;
+            }
+        };
+        for x in res.into_iter() {
+            if feed.iter().all(|y| y.uid != x.uid) {
+                feed.push(x);
+            }
+        }
+    }
+
+    if let Ok(coin) = agent.getUserInfo().await.and_then(|x| x.coinValue()) {
+        if let Ok(mut budget) = budget.lock() {
+            budget.setBalance(coin);
+        }
+    }
+
+    let torrentListRef = &torrentList;
+    let now = Local::now().timestamp();
+
+    let work = feed
+        .iter()
+        .map(|i| decideOne(agent, args, rules, torrentListRef, i, now));
+    let mut candidates = Vec::new();
+    let mut verdicts: HashMap<String, Verdict> = HashMap::new();
+    let mut scores: HashMap<String, f32> = HashMap::new();
+    let scorer = Scorer::new(args);
+    for (x, i) in futures::future::join_all(work)
+        .await
+        .into_iter()
+        .zip(feed.iter())
+    {
+        let x = match x {
+            Ok(x) => x,
+            Err(e) => {
+                warn!("promote:skip {}, {}", &i.title, e);
+                verdicts.insert(i.uid.to_owned(), Verdict::Failed(e.to_string()));
+                continue;
+            }
+        };
+        match x {
+            Ok(item) => {
+                let (score, reason) = scorer.score(item.0);
+                info!(
+                    "promote:score {:.2} for {}, {}",
+                    score, &item.0.title, reason
+                );
+                scores.insert(i.uid.to_owned(), score);
+                candidates.push(Candidate {
+                    score,
+                    GbSize: item.0.U2Info.GbSize,
+                    item,
+                });
+            }
+            Err(x) => {
+                verdicts.insert(i.uid.to_owned(), x);
+            }
+        }
+    }
+
+    let free = match args.lowWatermark {
+        Some(_) => Some(agent.getDiskFree().await? as f32 / GIB),
+        None => None,
+    };
+    let mut chosen = Vec::new();
+    let mut failed = None;
+    let mut selection = scorer.selection(pending, free);
+    for x in scorer.rank(candidates).into_iter() {
+        // magic over budget leaves the slot to the next candidate
+        let why = match selection.check(x.GbSize) {
+            Some(why) => why,
+            None => match book(agent, budget, &x.item, now).await {
+                Ok(Ok(cost)) => {
+                    selection.take(x.GbSize);
+                    chosen.push((x.item, cost));
+                    continue;
+                }
+                Ok(Err(why)) => why,
+                Err(e) => {
+                    verdicts.insert(x.item.0.uid.to_owned(), Verdict::Failed(e.to_string()));
+                    failed.get_or_insert(e);
+                    continue;
+                }
+            },
+        };
+        info!("promote:skip {}, {}", &x.item.0.title, why);
+        verdicts.insert(x.item.0.uid.to_owned(), Verdict::Skipped(why));
+    }
+
+    let dryRun = args.dryRun.unwrap_or(false);
+    // magic a dry run would have paid for, handed back once the cycle is done
+    let dryCost = Mutex::new(Vec::new());
+    let dryCostRef = &dryCost;
+    let work = chosen.into_iter().map(|(job, cost)| {
+        let uid = job.0.uid.to_owned();
+        async move {
+            let x = addOne(agent, budget, store, dryRun, dryCostRef, job, cost, now).await;
+            (uid, x)
+        }
+    });
+    let res = futures::future::join_all(work).await;
+    if let (Ok(dryCost), Ok(mut budget)) = (dryCost.lock(), budget.lock()) {
+        for cost in dryCost.iter() {
+            budget.refund(now, *cost);
+        }
+    }
+    for (uid, x) in res.into_iter() {
+        match x {
+            Ok(x) => {
+                verdicts.insert(uid, x);
+            }
+            Err(x) => {
+                verdicts.insert(uid, Verdict::Failed(x.to_string()));
+                failed.get_or_insert(x);
+            }
+        }
+    }
+    if let Ok(mut last) = last.write() {
+        *last = Some(
+            feed.iter()
+                .map(|x| FeedEntry {
+                    item: x.clone(),
+                    score: scores.get(&x.uid).copied(),
+                    verdict: verdicts
+                        .remove(&x.uid)
+                        .unwrap_or_else(|| Verdict::Skipped("not decided".to_string())),
+                })
+                .collect(),
+        );
+    }
+    if let Some(x) = failed {
+        return Err(x);
+    }
+    let cache = agent.cacheStats();
+    info!(
+        "promote:done, detail cache {} hits, {} static hits, {} misses",
+        cache.hits, cache.staticHits, cache.misses
+    );
+    Ok(())
+}
+
+/// previews the magic of a job and books its cost if the budget allows
+async fn book(
+    agent: &U2client,
+    budget: &Mutex<MagicBudget>,
+    (i, _, _, magic): &Job<'_>,
+    now: i64,
+) -> Result<Booked> {
+    let request = match magic {
+        Some(x) => x,
+        None => return Ok(Ok(None)),
+    };
+    let cost = agent.previewMagic(&i.uid, request).await?;
+    if !budget
+        .lock()
+        .map_err(|_| "promote:budget lock failed")?
+        .reserve(now, cost)
+    {
+        return Ok(Err(format!("magic costs {} UCoin over budget", cost)));
+    }
+    Ok(Ok(Some(cost)))
+}
+
+/// casts the magic of a chosen torrent, `cost` booked already, then adds it
+#[allow(clippy::too_many_arguments)]
+async fn addOne(
+    agent: &U2client,
+    budget: &Mutex<MagicBudget>,
+    store: &Store,
+    dryRun: bool,
+    dryCost: &Mutex<Vec<f64>>,
+    (i, rule, paused, magic): Job<'_>,
+    cost: Option<f64>,
+    now: i64,
+) -> Result<Verdict> {
+    let mut cast = None;
+    if let (Some(request), Some(cost)) = (magic, cost) {
+        if dryRun {
+            info!(
+                "promote:dry run, would apply {:?} for {}h on:{}, {} UCoin",
+                request.magic, request.hours, &i.title, cost
+            );
+            dryCost
+                .lock()
+                .map_err(|_| "promote:dry run lock failed")?
+                .push(cost);
+            cast = Some((format!("{:?}", request.magic), cost));
+        } else {
+            let x = agent.applyMagic(&i.uid, &request).await;
+            if let Err(x) = x {
+                if let Ok(mut budget) = budget.lock() {
+                    budget.refund(now, cost);
+                }
+                return Err(x);
+            }
+            info!("apply magic on:{}, {} UCoin", &i.title, cost);
+            // paid whether or not the add below works
+            recordMagic(store, i, &request, cost, now);
+            cast = Some((format!("{:?}", request.magic), cost));
+        }
+    }
+    if dryRun {
+        info!(
+            "promote:dry run, would add:{},{} GiB by rule {}",
+            &i.title, &i.U2Info.GbSize, rule
+        );
+        return Ok(Verdict::Added {
+            rule,
+            magic: cast,
+            dryRun,
+        });
+    }
+    info!(
+        "promote:new job:{},{} GiB by rule {}",
+        &i.title, &i.U2Info.GbSize, rule
+    );
+    agent.addTorrent(&i.url, paused).await?;
+    store.recordAdded(&AddedTorrent {
+        time: now,
+        uid: i.uid.to_owned(),
+        hash: i.U2Info.Hash.to_owned(),
+        title: i.title.to_owned(),
+        category: i.cat.to_owned(),
+        rule: rule.to_owned(),
+        magic: cast.as_ref().map(|x| x.0.to_owned()),
+        cost: cast.as_ref().map(|x| x.1),
+    })?;
+    Ok(Verdict::Added {
+        rule,
+        magic: cast,
+        dryRun,
+    })
+}
+
+/// adds a feed item picked in the dashboard, recorded under the rule `by hand`;
+/// refused if the client has it already
+pub async fn addByHand(agent: &U2client, args: &Config, store: &Store, i: &RssInfo) -> Result<()> {
+    let present = agent
+        .getWorkingTorrent()
+        .await?
+        .torrents
+        .iter()
+        .any(|x| x.hash_string.as_deref() == Some(i.U2Info.Hash.as_str()));
+    if present {
+        return Err(format!("addByHand:{} is in the client already", i.title).into());
+    }
+    if args.dryRun.unwrap_or(false) {
+        info!("promote:dry run, would add by hand:{}", &i.title);
+        return Ok(());
+    }
+    info!("promote:add by hand:{},{} GiB", &i.title, &i.U2Info.GbSize);
+    agent.addTorrent(&i.url, false).await?;
+    store.recordAdded(&AddedTorrent {
+        time: Local::now().timestamp(),
+        uid: i.uid.to_owned(),
+        hash: i.U2Info.Hash.to_owned(),
+        title: i.title.to_owned(),
+        category: i.cat.to_owned(),
+        rule: BY_HAND.to_string(),
+        magic: None,
+        cost: None,
+    })
+}
+
+/// casts free download on a feed item picked in the dashboard, within the budget;
+/// returns the cost in UCoin
+pub async fn magicByHand(
+    agent: &U2client,
+    args: &Config,
+    budget: &Mutex<MagicBudget>,
+    store: &Store,
+    i: &RssInfo,
+) -> Result<f64> {
+    let request = MagicRequest {
+        magic: Magic::Free,
+        target: MagicTarget::Own,
+        hours: magicHours(i.U2Info.GbSize),
+    };
+    let cost = agent.previewMagic(&i.uid, &request).await?;
+    if args.dryRun.unwrap_or(false) {
+        info!(
+            "promote:dry run, would apply {:?} for {}h by hand on:{}, {} UCoin",
+            request.magic, request.hours, &i.title, cost
+        );
+        return Ok(cost);
+    }
+    let now = Local::now().timestamp();
+    if !budget
+        .lock()
+        .map_err(|_| "magicByHand:budget lock failed")?
+        .reserve(now, cost)
+    {
+        return Err(format!("magicByHand:{} UCoin over budget", cost).into());
+    }
+    if let Err(x) = agent.applyMagic(&i.uid, &request).await {
+        if let Ok(mut budget) = budget.lock() {
+            budget.refund(now, cost);
+        }
+        return Err(x);
+    }
+    info!("apply magic by hand on:{}, {} UCoin", &i.title, cost);
+    recordMagic(store, i, &request, cost, now);
+    Ok(cost)
+}
+
+/// keeps the UCoin spent in the store, a failure is only logged as the magic is cast already
+fn recordMagic(store: &Store, i: &RssInfo, request: &MagicRequest, cost: f64, now: i64) {
+    let x = store.recordMagic(&MagicCast {
+        time: now,
+        uid: i.uid.to_owned(),
+        hash: i.U2Info.Hash.to_owned(),
+        title: i.title.to_owned(),
+        magic: format!("{:?}", request.magic),
+        cost,
+    });
+    if let Err(x) = x {
+        error!("promote:record magic on {} failed, {}", &i.title, x);
+    }
+}
+
+/// what the rules make of a torrent not in the client yet
+async fn decideOne<'a>(
+    agent: &U2client,
+    args: &Config,
+    rules: &RuleEngine,
+    torrentList: &HashSet<String>,
+    i: &'a RssInfo,
+    now: i64,
+) -> Result<Decided<'a>> {
+    if torrentList.contains(&i.U2Info.Hash) {
+        return Ok(Err(Verdict::Present));
+    }
+    let hungry =
+        rules.asksHungry(i, now) && isHungry(agent, &i.uid, args.minConnectableLeecher).await?;
+    let (rule, decision) = rules.decide(i, now, hungry);
+    match decision {
+        Decision::Skip => Ok(Err(Verdict::Skipped(format!("by rule {}", rule)))),
+        Decision::Add { paused, magic } => Ok(Ok((i, rule, paused, magic))),
+    }
+}
+
+/// whether enough connectable leechers are still far from done,
+/// which keeps a torrent worth joining even if the average progress is high
+async fn isHungry(
+    agent: &U2client,
+    uid: &str,
+    minConnectableLeecher: Option<usize>,
+) -> Result<bool> {
+    let min = match minConnectableLeecher {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+    let peers = agent.getTorrentPeers(uid).await?;
+    let hungry = peers
+        .iter()
+        .filter(|x| x.connectable && x.progress < 0.5)
+        .count();
+    Ok(hungry >= min)
+}
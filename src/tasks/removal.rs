@@ -0,0 +1,221 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::store::Store;
+use crate::tasks::GIB;
+use crate::torrentLib::response::Torrent;
+use crate::u2client::types::Config;
+use crate::Result;
+
+/// samples of `uploadedEver` by torrent hash, oldest first
+#[derive(Default)]
+pub struct UploadHistory {
+    samples: HashMap<String, Vec<(i64, i64)>>,
+}
+
+impl UploadHistory {
+    /// the samples the store keeps after `since`, with the one before as the baseline
+    pub fn load(store: &Store, since: i64) -> Result<UploadHistory> {
+        let mut samples: HashMap<String, Vec<(i64, i64)>> = HashMap::new();
+        for (time, hash, uploaded) in store.uploadsSince(since)?.into_iter() {
+            samples.entry(hash).or_default().push((time, uploaded));
+        }
+        Ok(UploadHistory { samples })
+    }
+
+    /// bytes uploaded after `since`, counted from the oldest sample if the history is shorter
+    pub fn uploadedSince(&self, x: &Torrent, since: i64) -> i64 {
+        let now = x.uploaded_ever.unwrap_or(0);
+        if x.added_date.unwrap_or(0) >= since {
+            return now;
+        }
+        let base = x
+            .hash_string
+            .as_ref()
+            .and_then(|hash| self.samples.get(hash))
+            .and_then(|y| y.iter().rev().find(|s| s.0 <= since).or_else(|| y.first()))
+            .map(|s| s.1)
+            .unwrap_or(now);
+        (now - base).max(0)
+    }
+}
+
+/// orders torrents for removal, a smaller key goes first
+pub trait RemovalStrategy: Send + Sync {
+    fn key(&self, x: &Torrent, history: &UploadHistory, now: i64) -> f64;
+}
+
+/// least uploaded within the window
+pub struct LeastUploaded {
+    pub window: i64,
+}
+
+impl RemovalStrategy for LeastUploaded {
+    fn key(&self, x: &Torrent, history: &UploadHistory, now: i64) -> f64 {
+        history.uploadedSince(x, now - self.window) as f64
+    }
+}
+
+/// least ratio gained within the window per GiB of disk
+pub struct RatioGain {
+    pub window: i64,
+}
+
+impl RemovalStrategy for RatioGain {
+    fn key(&self, x: &Torrent, history: &UploadHistory, now: i64) -> f64 {
+        let size = x.total_size.unwrap_or(0) as f64;
+        if size <= 0.0 {
+            return 0.0;
+        }
+        history.uploadedSince(x, now - self.window) as f64 / size / (size / GIB as f64)
+    }
+}
+
+/// completed longest ago, unfinished ones last
+pub struct OldestDone;
+
+impl RemovalStrategy for OldestDone {
+    fn key(&self, x: &Torrent, _: &UploadHistory, _: i64) -> f64 {
+        match x.done_date {
+            Some(x) if x > 0 => x as f64,
+            _ => f64::MAX,
+        }
+    }
+}
+
+/// least recently active
+pub struct LeastActive;
+
+impl RemovalStrategy for LeastActive {
+    fn key(&self, x: &Torrent, _: &UploadHistory, _: i64) -> f64 {
+        x.activity_date.unwrap_or(0) as f64
+    }
+}
+
+pub struct Largest;
+
+impl RemovalStrategy for Largest {
+    fn key(&self, x: &Torrent, _: &UploadHistory, _: i64) -> f64 {
+        -(x.total_size.unwrap_or(0) as f64)
+    }
+}
+
+/// fewest peers downloading from us right now
+pub struct FewestPeers;
+
+impl RemovalStrategy for FewestPeers {
+    fn key(&self, x: &Torrent, _: &UploadHistory, _: i64) -> f64 {
+        x.peers_getting_from_us.unwrap_or(0) as f64
+    }
+}
+
+pub struct OldestAdded;
+
+impl RemovalStrategy for OldestAdded {
+    fn key(&self, x: &Torrent, _: &UploadHistory, _: i64) -> f64 {
+        x.added_date.unwrap_or(0) as f64
+    }
+}
+
+/// picks torrents to remove by the configured strategies, each breaking the ties of the one before
+pub struct Removal {
+    strategies: Vec<Box<dyn RemovalStrategy>>,
+    /// seconds of upload samples kept in the store
+    window: i64,
+    /// `None` frees just enough to get under `maxSize`
+    count: Option<usize>,
+}
+
+impl Removal {
+    pub fn new(args: &Config) -> Result<Removal> {
+        let window = (args.removeWindow.unwrap_or(7.0) * 86400.0) as i64;
+        let names = match args.removeStrategy {
+            Some(ref x) if !x.is_empty() => x.clone(),
+            _ => vec!["fewestPeers".to_string(), "oldestAdded".to_string()],
+        };
+        let mut strategies = Vec::new();
+        for i in names.iter() {
+            strategies.push(Removal::strategy(i, window)?);
+        }
+        let count = match args.removeMode.as_deref() {
+            Some("enough") => None,
+            Some("count") | None => Some(args.removeCount.unwrap_or(5)),
+            Some(x) => return Err(format!("removal:unknown removeMode {}", x).into()),
+        };
+        Ok(Removal {
+            strategies,
+            window,
+            count,
+        })
+    }
+
+    pub fn strategy(name: &str, window: i64) -> Result<Box<dyn RemovalStrategy>> {
+        Ok(match name {
+            "leastUploaded" => Box::new(LeastUploaded { window }),
+            "ratioGain" => Box::new(RatioGain { window }),
+            "oldestDone" => Box::new(OldestDone),
+            "leastActive" => Box::new(LeastActive),
+            "largest" => Box::new(Largest),
+            "fewestPeers" => Box::new(FewestPeers),
+            "oldestAdded" => Box::new(OldestAdded),
+            x => return Err(format!("removal:unknown strategy {}", x).into()),
+        })
+    }
+
+    /// samples `uploadedEver` into the store for the strategies that look at a window
+    pub fn record(&self, store: &Store, now: i64, torrents: &[Torrent]) -> Result<()> {
+        let samples: Vec<(&str, i64)> = torrents
+            .iter()
+            .filter_map(|x| Some((x.hash_string.as_deref()?, x.uploaded_ever?)))
+            .collect();
+        store.recordUploads(now, &samples, self.window)
+    }
+
+    /// torrents in `protected` are never picked, `excess` is the bytes over `maxSize`
+    pub fn pick(
+        &self,
+        store: &Store,
+        torrents: Vec<Torrent>,
+        protected: &HashSet<String>,
+        excess: i64,
+        now: i64,
+    ) -> Result<Vec<Torrent>> {
+        let history = UploadHistory::load(store, now - self.window)?;
+        let mut keyed: Vec<(Vec<f64>, Torrent)> = torrents
+            .into_iter()
+            .filter(|x| match x.hash_string {
+                Some(ref x) => !protected.contains(x),
+                None => false,
+            })
+            .map(|x| {
+                let keys = self
+                    .strategies
+                    .iter()
+                    .map(|s| s.key(&x, &history, now))
+                    .collect();
+                (keys, x)
+            })
+            .collect();
+        keyed.sort_by(|a, b| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .map(|(x, y)| x.partial_cmp(y).unwrap_or(Ordering::Equal))
+                .find(|x| *x != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+        let sorted = keyed.into_iter().map(|x| x.1);
+        Ok(match self.count {
+            Some(count) => sorted.take(count).collect(),
+            None => {
+                let mut freed = 0;
+                sorted
+                    .take_while(|x| {
+                        let enough = freed >= excess;
+                        freed += x.total_size.unwrap_or(0);
+                        !enough
+                    })
+                    .collect()
+            }
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::u2client::client::{MAGIC_MAX_HOURS, MAGIC_MIN_HOURS};
+use crate::u2client::types::{Config, Magic, MagicRequest, MagicTarget, RssInfo};
+use crate::Result;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Action {
+    Add,
+    AddPaused,
+    Magic,
+    Skip,
+}
+
+/// the order rules are tried in
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleMode {
+    First,
+    Priority,
+}
+
+/// every bound is inclusive unless `exclusive`, a missing one always matches
+#[derive(Debug, Deserialize, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub priority: Option<i32>,
+
+    pub category: Option<Vec<String>>,
+    pub title: Option<String>,
+    /// GiB
+    pub minSize: Option<f32>,
+    pub maxSize: Option<f32>,
+    pub minUploadFX: Option<f32>,
+    pub maxUploadFX: Option<f32>,
+    pub minDownloadFX: Option<f32>,
+    pub maxDownloadFX: Option<f32>,
+    pub minSeeder: Option<i32>,
+    pub maxSeeder: Option<i32>,
+    pub minLeecher: Option<i32>,
+    pub maxLeecher: Option<i32>,
+    pub minAvgProgress: Option<f32>,
+    pub maxAvgProgress: Option<f32>,
+    /// hours since publishing
+    pub maxAge: Option<f32>,
+    /// the min and max bounds exclude their value
+    pub exclusive: Option<bool>,
+    /// whether at least `minConnectableLeecher` connectable leechers are below 50%
+    pub hungry: Option<bool>,
+
+    pub action: Action,
+    pub magic: Option<Magic>,
+    pub hours: Option<i32>,
+}
+
+impl Rule {
+    fn new(name: &str, action: Action) -> Rule {
+        Rule {
+            name: name.to_string(),
+            priority: None,
+            category: None,
+            title: None,
+            minSize: None,
+            maxSize: None,
+            minUploadFX: None,
+            maxUploadFX: None,
+            minDownloadFX: None,
+            maxDownloadFX: None,
+            minSeeder: None,
+            maxSeeder: None,
+            minLeecher: None,
+            maxLeecher: None,
+            minAvgProgress: None,
+            maxAvgProgress: None,
+            maxAge: None,
+            exclusive: None,
+            hungry: None,
+            action,
+            magic: None,
+            hours: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Decision {
+    Add {
+        paused: bool,
+        magic: Option<MagicRequest>,
+    },
+    Skip,
+}
+
+/// long enough to download `GbSize` at 5 MiB/s, within what the site allows
+pub fn magicHours(GbSize: f32) -> i32 {
+    ((GbSize * 1024.0 / 5.0 / 3600.0).floor() as i32 + 1).clamp(MAGIC_MIN_HOURS, MAGIC_MAX_HOURS)
+}
+
+pub struct RuleEngine {
+    rules: Vec<(Rule, Option<Regex>)>,
+}
+
+fn within<T: PartialOrd>(x: T, min: Option<T>, max: Option<T>, exclusive: bool) -> bool {
+    if exclusive {
+        min.iter().all(|min| x > *min) && max.iter().all(|max| x < *max)
+    } else {
+        min.iter().all(|min| x >= *min) && max.iter().all(|max| x <= *max)
+    }
+}
+
+impl RuleEngine {
+    /// the configured rules, or the ones matching the historic behaviour if there are none
+    pub fn new(args: &Config) -> Result<RuleEngine> {
+        let mut rules = match args.rules {
+            Some(ref x) if !x.is_empty() => x.clone(),
+            _ => RuleEngine::defaults(args),
+        };
+        if args.ruleMode == Some(RuleMode::Priority) {
+            rules.sort_by_key(|x| std::cmp::Reverse(x.priority.unwrap_or(0)));
+        }
+        let mut ret = Vec::new();
+        for i in rules.into_iter() {
+            if i.action == Action::Magic && i.magic.is_none() {
+                return Err(format!("rule {}:magic action without magic", i.name).into());
+            }
+            if let Some(x) = i.hours {
+                if !(MAGIC_MIN_HOURS..=MAGIC_MAX_HOURS).contains(&x) {
+                    return Err(format!(
+                        "rule {}:{} hours out of {}..={}",
+                        i.name, x, MAGIC_MIN_HOURS, MAGIC_MAX_HOURS
+                    )
+                    .into());
+                }
+            }
+            let title = match i.title {
+                Some(ref x) => Some(Regex::new(x)?),
+                None => None,
+            };
+            ret.push((i, title));
+        }
+        Ok(RuleEngine { rules: ret })
+    }
+
+    fn defaults(args: &Config) -> Vec<Rule> {
+        let mut noSeeder = Rule::new("no seeder", Action::Skip);
+        noSeeder.maxSeeder = Some(0);
+        let mut popular = Rule::new("popular", Action::Skip);
+        popular.minAvgProgress = Some(0.5);
+        popular.exclusive = Some(true);
+        popular.hungry = Some(false);
+        let mut magic = Rule::new("magic", Action::Magic);
+        magic.minDownloadFX = args.downloadFxFilter.or(Some(0.0));
+        magic.minSize = args.GBSizeFilter.or(Some(0.0));
+        magic.exclusive = Some(true);
+        magic.magic = Some(Magic::HalfDown);
+        vec![noSeeder, popular, magic, Rule::new("default", Action::Add)]
+    }
+
+    /// whether `rule` matches, leaving out the `hungry` condition
+    pub fn matches(rule: &Rule, title: &Option<Regex>, x: &RssInfo, now: i64) -> bool {
+        let info = &x.U2Info;
+        let ex = rule.exclusive.unwrap_or(false);
+        rule.category.iter().all(|cat| cat.contains(&x.cat))
+            && title.iter().all(|reg| reg.is_match(&x.title))
+            && within(info.GbSize, rule.minSize, rule.maxSize, ex)
+            && within(info.uploadFX, rule.minUploadFX, rule.maxUploadFX, ex)
+            && within(info.downloadFX, rule.minDownloadFX, rule.maxDownloadFX, ex)
+            && within(info.seeder, rule.minSeeder, rule.maxSeeder, ex)
+            && within(info.leecher, rule.minLeecher, rule.maxLeecher, ex)
+            && within(
+                info.avgProgress,
+                rule.minAvgProgress,
+                rule.maxAvgProgress,
+                ex,
+            )
+            && rule.maxAge.iter().all(|age| match x.time {
+                Some(time) => (now - time) as f32 <= age * 3600.0,
+                None => false,
+            })
+    }
+
+    /// whether deciding on `x` needs to know if it is hungry, which takes a peer list
+    pub fn asksHungry(&self, x: &RssInfo, now: i64) -> bool {
+        self.rules
+            .iter()
+            .find(|(rule, title)| RuleEngine::matches(rule, title, x, now))
+            .is_some_and(|(rule, _)| rule.hungry.is_some())
+    }
+
+    /// the first matching rule and what it asks for, torrents no rule matches are skipped
+    pub fn decide(&self, x: &RssInfo, now: i64, hungry: bool) -> (String, Decision) {
+        for (rule, title) in self.rules.iter() {
+            if !RuleEngine::matches(rule, title, x, now) || rule.hungry.iter().any(|y| *y != hungry)
+            {
+                continue;
+            }
+            let decision = match rule.action {
+                Action::Skip => Decision::Skip,
+                Action::Add => Decision::Add {
+                    paused: false,
+                    magic: None,
+                },
+                Action::AddPaused => Decision::Add {
+                    paused: true,
+                    magic: None,
+                },
+                Action::Magic => Decision::Add {
+                    paused: false,
+                    magic: rule.magic.map(|magic| MagicRequest {
+                        magic,
+                        target: MagicTarget::Own,
+                        hours: rule.hours.unwrap_or_else(|| magicHours(x.U2Info.GbSize)),
+                    }),
+                },
+            };
+            return (rule.name.to_owned(), decision);
+        }
+        ("no rule".to_string(), Decision::Skip)
+    }
+}
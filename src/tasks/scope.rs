@@ -0,0 +1,96 @@
+use regex::Regex;
+
+use crate::store::Store;
+use crate::torrentLib::response::Torrent;
+use crate::u2client::types::Config;
+use crate::Result;
+
+/// tracker hosts of U2
+const U2_TRACKERS: [&str; 2] = ["dmhy.best", "dmhy.org"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// added by the tool, as recorded in the store
+    Added,
+    /// added by the tool or announcing to U2
+    U2,
+    All,
+}
+
+/// which torrents in the client maintain and expire may touch
+pub struct Scope {
+    mode: Mode,
+    /// name or hash patterns always managed
+    allow: Vec<Regex>,
+    /// name or hash patterns never managed, even if allowed
+    deny: Vec<Regex>,
+}
+
+/// whether `x` announces to U2
+pub fn announcesToU2(x: &Torrent) -> bool {
+    x.trackers
+        .iter()
+        .flatten()
+        .any(|y| isU2Tracker(&y.announce))
+}
+
+fn isU2Tracker(announce: &str) -> bool {
+    match reqwest::Url::parse(announce) {
+        Ok(url) => url.host_str().iter().any(|host| {
+            U2_TRACKERS
+                .iter()
+                .any(|x| host == x || host.ends_with(&format!(".{}", x)))
+        }),
+        Err(_) => false,
+    }
+}
+
+fn compile(x: &Option<Vec<String>>) -> Result<Vec<Regex>> {
+    let mut ret = Vec::new();
+    for i in x.iter().flatten() {
+        ret.push(Regex::new(i)?);
+    }
+    Ok(ret)
+}
+
+impl Scope {
+    pub fn new(args: &Config) -> Result<Scope> {
+        let mode = match args.manage.as_deref() {
+            Some("added") => Mode::Added,
+            Some("u2") | None => Mode::U2,
+            Some("all") => Mode::All,
+            Some(x) => return Err(format!("scope:unknown manage {}", x).into()),
+        };
+        Ok(Scope {
+            mode,
+            allow: compile(&args.allowList)?,
+            deny: compile(&args.denyList)?,
+        })
+    }
+
+    pub fn contains(&self, x: &Torrent, store: &Store) -> Result<bool> {
+        let name = x.name.as_deref().unwrap_or("");
+        let hash = x.hash_string.as_deref().unwrap_or("");
+        let listed = |list: &Vec<Regex>| list.iter().any(|y| y.is_match(name) || y.is_match(hash));
+        if listed(&self.deny) {
+            return Ok(false);
+        }
+        if listed(&self.allow) || self.mode == Mode::All {
+            return Ok(true);
+        }
+        if store.added(hash)?.is_some() {
+            return Ok(true);
+        }
+        Ok(self.mode == Mode::U2 && announcesToU2(x))
+    }
+
+    pub fn filter(&self, torrents: Vec<Torrent>, store: &Store) -> Result<Vec<Torrent>> {
+        let mut ret = Vec::new();
+        for x in torrents.into_iter() {
+            if self.contains(&x, store)? {
+                ret.push(x);
+            }
+        }
+        Ok(ret)
+    }
+}
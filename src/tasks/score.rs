@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+
+use serde::Deserialize;
+
+use crate::u2client::types::{Config, RssInfo};
+
+/// weights of the terms summed into a score, a missing one keeps its default
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ScoreWeights {
+    /// leechers per seeder
+    pub demand: f32,
+    pub uploadFX: f32,
+    /// the part of the download that is free
+    pub free: f32,
+    /// ln(1 + GiB)
+    pub size: f32,
+    /// 1 - average progress of the peers
+    pub progress: f32,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            demand: 1.0,
+            uploadFX: 1.0,
+            free: 1.0,
+            size: 0.5,
+            progress: 1.0,
+        }
+    }
+}
+
+pub struct Candidate<T> {
+    pub score: f32,
+    pub GbSize: f32,
+    pub item: T,
+}
+
+/// ranks the candidates of a cycle by expected upload and keeps the best within the budgets
+pub struct Scorer {
+    weights: ScoreWeights,
+    maxAdd: Option<usize>,
+    /// GiB added per cycle
+    maxAddSize: Option<f32>,
+    /// GiB left to download over the whole client
+    maxPendingSize: Option<f32>,
+    /// GiB to leave free on disk
+    lowWatermark: Option<f32>,
+}
+
+impl Scorer {
+    pub fn new(args: &Config) -> Scorer {
+        Scorer {
+            weights: args.scoreWeights.clone().unwrap_or_default(),
+            maxAdd: args.maxAddPerCycle,
+            maxAddSize: args.maxAddSize,
+            maxPendingSize: args.maxPendingSize,
+            lowWatermark: args.lowWatermark,
+        }
+    }
+
+    /// the score and the terms it is made of
+    pub fn score(&self, x: &RssInfo) -> (f32, String) {
+        let info = &x.U2Info;
+        let w = &self.weights;
+        let terms = [
+            (
+                "demand",
+                w.demand,
+                info.leecher as f32 / (info.seeder.max(0) + 1) as f32,
+            ),
+            ("uploadFX", w.uploadFX, info.uploadFX),
+            ("free", w.free, (1.0 - info.downloadFX).max(0.0)),
+            ("size", w.size, info.GbSize.max(0.0).ln_1p()),
+            ("progress", w.progress, (1.0 - info.avgProgress).max(0.0)),
+        ];
+        let score = terms.iter().map(|(_, w, x)| w * x).sum();
+        let reason = terms
+            .iter()
+            .map(|(name, w, x)| format!("{} {:.2}*{:.2}", name, x, w))
+            .collect::<Vec<String>>()
+            .join(", ");
+        (score, reason)
+    }
+
+    /// best first
+    pub fn rank<T>(&self, mut candidates: Vec<Candidate<T>>) -> Vec<Candidate<T>> {
+        candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        candidates
+    }
+
+    /// `pending` is the GiB the client still has to download and `free` the GiB free on disk
+    pub fn selection(&self, pending: f32, free: Option<f32>) -> Selection<'_> {
+        Selection {
+            scorer: self,
+            count: 0,
+            size: 0.0,
+            pending,
+            free,
+        }
+    }
+}
+
+/// what the candidates taken so far use up of the budgets of a cycle
+pub struct Selection<'a> {
+    scorer: &'a Scorer,
+    count: usize,
+    /// GiB
+    size: f32,
+    pending: f32,
+    free: Option<f32>,
+}
+
+impl Selection<'_> {
+    /// why a candidate of `GbSize` GiB is left out, `None` if it fits
+    pub fn check(&self, GbSize: f32) -> Option<String> {
+        let x = self.scorer;
+        let left = self.free.unwrap_or(f32::MAX) - self.pending - self.size;
+        if x.maxAdd.iter().any(|max| self.count >= *max) {
+            Some(format!("not in the top {}", self.count))
+        } else if x.maxAddSize.iter().any(|max| self.size + GbSize > *max) {
+            Some(format!("{} GiB added this cycle already", self.size))
+        } else if x
+            .maxPendingSize
+            .iter()
+            .any(|max| self.pending + self.size + GbSize > *max)
+        {
+            Some(format!("{} GiB left to download", self.pending + self.size))
+        } else if x.lowWatermark.iter().any(|low| left - GbSize < *low) {
+            Some(format!("{} GiB free once downloaded, would not fit", left))
+        } else {
+            None
+        }
+    }
+
+    pub fn take(&mut self, GbSize: f32) {
+        self.count += 1;
+        self.size += GbSize;
+    }
+}
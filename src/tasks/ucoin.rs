@@ -0,0 +1,101 @@
+use std::sync::{Mutex, RwLock};
+
+use chrono::Local;
+use log::info;
+
+use crate::store::Store;
+use crate::tasks::budget::{BudgetPeriod, MagicBudget};
+use crate::u2client::client::U2client;
+use crate::u2client::types::{UCoinEntry, UCoinSummary};
+use crate::Result;
+
+/// what the UI shows of the UCoin log, which is kept in the store
+#[derive(Debug, Clone, Default)]
+pub struct UCoinLedger {
+    hourly: Option<f64>,
+    today: UCoinSummary,
+}
+
+/// (time, cost) of every magic cast
+pub fn magicSpend(entries: &[UCoinEntry]) -> Vec<(i64, f64)> {
+    entries
+        .iter()
+        .filter(|x| x.isMagic() && x.amount < 0.0)
+        .map(|x| (x.time, -x.amount))
+        .collect()
+}
+
+/// income, magic spend and net change since `from`
+pub fn summary(entries: &[UCoinEntry], from: i64) -> UCoinSummary {
+    let mut ret = UCoinSummary::default();
+    for x in entries.iter().filter(|x| x.time >= from) {
+        if x.amount > 0.0 {
+            ret.income += x.amount;
+        } else if x.isMagic() {
+            ret.magic -= x.amount;
+        }
+        ret.net += x.amount;
+    }
+    ret
+}
+
+impl UCoinLedger {
+    pub fn new() -> UCoinLedger {
+        UCoinLedger::default()
+    }
+
+    /// recompute today from the store, returns the magic spend of the longest budget window
+    pub fn reload(&mut self, store: &Store, now: i64) -> Result<Vec<(i64, f64)>> {
+        let today = Local::today().and_hms(0, 0, 0).timestamp();
+        let entries = store.ucoinSince(today.min(now - BudgetPeriod::Weekly.seconds()))?;
+        self.today = UCoinSummary {
+            hourly: self.hourly,
+            ..summary(&entries, today)
+        };
+        Ok(magicSpend(&entries))
+    }
+
+    pub fn today(&self) -> UCoinSummary {
+        self.today.clone()
+    }
+}
+
+/// pages fetched when the store has nothing in common with the site yet
+const MAX_PAGES: u32 = 10;
+
+pub async fn handleOne(
+    agent: &U2client,
+    store: &Store,
+    ledger: &RwLock<UCoinLedger>,
+    budget: &Mutex<MagicBudget>,
+) -> Result<()> {
+    // the log is newest first, a page reaching back to what we have is the last one
+    let latest = store.ucoinLatest()?;
+    let mut fetched = Vec::new();
+    for page in 0..MAX_PAGES {
+        let res = agent.getUCoinLog(page).await?;
+        let done = res.is_empty() || res.iter().any(|x| Some(x.time) <= latest);
+        fetched.extend(res);
+        if done {
+            break;
+        }
+    }
+    let hourly = agent.getBonusRate().await.ok();
+
+    let cnt = store.recordUCoin(&fetched)?;
+    let now = Local::now().timestamp();
+    let mut ledger = ledger.write().map_err(|_| "ucoin:ledger lock failed")?;
+    if hourly.is_some() {
+        ledger.hourly = hourly;
+    }
+    let spend = ledger.reload(store, now)?;
+    if let Ok(mut budget) = budget.lock() {
+        budget.sync(now, spend);
+    }
+    let today = ledger.today();
+    info!(
+        "ucoin:{} new entries, today income {:.2}, magic {:.2}, net {:.2}",
+        cnt, today.income, today.magic, today.net
+    );
+    Ok(())
+}
@@ -0,0 +1,508 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{sleep, Duration};
+
+use crate::Result;
+
+const SESSION_ID: &str = "mock-session-id";
+
+#[derive(Debug, Clone)]
+pub struct MockTorrent {
+    pub id: i64,
+    pub name: String,
+    pub hash: String,
+    pub totalSize: i64,
+    pub addedDate: i64,
+    pub activityDate: i64,
+    pub doneDate: i64,
+    pub percentDone: f32,
+    pub status: i64,
+    pub peersGettingFromUs: i64,
+    pub uploadedEver: i64,
+    pub downloadDir: String,
+    pub trackers: Vec<String>,
+    /// (path, length, bytes completed)
+    pub files: Vec<(String, i64, i64)>,
+    /// (address, client name)
+    pub peers: Vec<(String, String)>,
+}
+
+impl MockTorrent {
+    pub fn new(id: i64, name: &str, totalSize: i64, addedDate: i64) -> MockTorrent {
+        MockTorrent {
+            id,
+            name: name.to_string(),
+            hash: MockTorrent::hashOf(name),
+            totalSize,
+            addedDate,
+            activityDate: addedDate,
+            doneDate: addedDate,
+            percentDone: 1.0,
+            status: 6,
+            peersGettingFromUs: 0,
+            uploadedEver: 0,
+            downloadDir: String::new(),
+            trackers: vec!["https://daydream.dmhy.best/announce?secure=mock".to_string()],
+            files: vec![(name.to_string(), totalSize, totalSize)],
+            peers: Vec::new(),
+        }
+    }
+
+    pub fn hashOf(name: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        let x = hasher.finish();
+        format!("{:016x}{:016x}{:08x}", x, !x, x as u32)
+    }
+
+    fn toJson(&self) -> Value {
+        json!({
+            "id": self.id,
+            "name": self.name,
+            "hashString": self.hash,
+            "totalSize": self.totalSize,
+            "sizeWhenDone": self.totalSize,
+            "leftUntilDone": ((1.0 - self.percentDone) * self.totalSize as f32) as i64,
+            "addedDate": self.addedDate,
+            "activityDate": self.activityDate,
+            "doneDate": self.doneDate,
+            "percentDone": self.percentDone,
+            "status": self.status,
+            "isFinished": false,
+            "isStalled": false,
+            "error": 0,
+            "errorString": "",
+            "eta": -1,
+            "peersConnected": self.peersGettingFromUs,
+            "peersGettingFromUs": self.peersGettingFromUs,
+            "peersSendingToUs": 0,
+            "rateDownload": 0,
+            "rateUpload": 0,
+            "uploadedEver": self.uploadedEver,
+            "uploadRatio": self.uploadedEver as f32 / self.totalSize.max(1) as f32,
+            "downloadDir": self.downloadDir,
+            "trackers": self
+                .trackers
+                .iter()
+                .enumerate()
+                .map(|(id, announce)| json!({ "id": id, "announce": announce }))
+                .collect::<Vec<Value>>(),
+            "files": self
+                .files
+                .iter()
+                .map(|(name, length, done)| {
+                    json!({ "name": name, "length": length, "bytesCompleted": done })
+                })
+                .collect::<Vec<Value>>(),
+            "fileStats": self
+                .files
+                .iter()
+                .map(|(_, _, done)| json!({ "bytesCompleted": done, "wanted": true, "priority": 0 }))
+                .collect::<Vec<Value>>(),
+            "peers": self
+                .peers
+                .iter()
+                .map(|(address, client)| {
+                    json!({
+                        "address": address,
+                        "clientName": client,
+                        "flagStr": "UE",
+                        "progress": 0.5,
+                        "rateToClient": 0,
+                        "rateToPeer": 1024,
+                    })
+                })
+                .collect::<Vec<Value>>(),
+            "trackerStats": self
+                .trackers
+                .iter()
+                .map(|announce| {
+                    json!({
+                        "host": reqwest::Url::parse(announce).ok().and_then(|x| x.host_str().map(|x| x.to_string())),
+                        "announce": announce,
+                        "lastAnnounceResult": "Success",
+                        "lastAnnounceSucceeded": true,
+                        "lastAnnounceTime": self.activityDate,
+                        "nextAnnounceTime": self.activityDate + 1800,
+                        "seederCount": 1,
+                        "leecherCount": 0,
+                    })
+                })
+                .collect::<Vec<Value>>(),
+        })
+    }
+
+    /// only the fields asked for, as transmission does
+    fn toJsonFields(&self, fields: &Value) -> Value {
+        let x = self.toJson();
+        match (fields.as_array(), x.as_object()) {
+            (Some(fields), Some(map)) => Value::Object(
+                map.iter()
+                    .filter(|(key, _)| fields.iter().any(|y| y.as_str() == Some(key.as_str())))
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .collect(),
+            ),
+            _ => x,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MockState {
+    pub torrents: Vec<MockTorrent>,
+    pub nextId: i64,
+    pub removed: Vec<(String, bool)>,
+    pub actions: Vec<(String, String)>,
+    pub freeSpace: u64,
+    /// the paths free-space was asked about
+    pub freeSpacePaths: Vec<String>,
+    pub handshakes: u32,
+}
+
+/// an in-memory transmission daemon speaking just enough of the RPC protocol for tests
+pub struct FakeTransmission {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl FakeTransmission {
+    pub async fn start() -> Result<FakeTransmission> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/transmission/rpc", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(MockState {
+            nextId: 1,
+            freeSpace: 1 << 40,
+            ..MockState::default()
+        }));
+        let stateSep = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&stateSep);
+                tokio::spawn(async move {
+                    let _ = FakeTransmission::serve(stream, state).await;
+                });
+            }
+        });
+        Ok(FakeTransmission { url, state })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn push(&self, mut torrent: MockTorrent) {
+        let mut state = self.state.lock().unwrap();
+        state.nextId = state.nextId.max(torrent.id + 1);
+        if torrent.id == 0 {
+            torrent.id = state.nextId;
+            state.nextId += 1;
+        }
+        state.torrents.push(torrent);
+    }
+
+    pub fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    async fn serve(mut stream: TcpStream, state: Arc<Mutex<MockState>>) -> Result<()> {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let headerEnd = loop {
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            if let Some(pos) = buf.windows(4).position(|x| x == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&buf[..headerEnd]).to_lowercase();
+        let mut contentLength = 0usize;
+        let mut sessionId = None;
+        for line in head.lines() {
+            if let Some(x) = line.strip_prefix("content-length:") {
+                contentLength = x.trim().parse()?;
+            }
+            if let Some(x) = line.strip_prefix("x-transmission-session-id:") {
+                sessionId = Some(x.trim().to_string());
+            }
+        }
+        while buf.len() < headerEnd + contentLength {
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+
+        let response = if sessionId.as_deref() != Some(SESSION_ID) {
+            state.lock().unwrap().handshakes += 1;
+            let body = "<h1>409: Conflict</h1>";
+            format!(
+                "HTTP/1.1 409 Conflict\r\nX-Transmission-Session-Id: {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                SESSION_ID,
+                body.len(),
+                body
+            )
+        } else {
+            let request: Value = serde_json::from_slice(&buf[headerEnd..])?;
+            let body = FakeTransmission::handle(&request, &mut state.lock().unwrap()).to_string();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        };
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+    fn selected(args: &Value, state: &MockState) -> Vec<String> {
+        match args.get("ids").and_then(|x| x.as_array()) {
+            Some(ids) => {
+                let ids: HashSet<String> = ids
+                    .iter()
+                    .map(|x| match x {
+                        Value::String(x) => x.to_owned(),
+                        x => x.to_string(),
+                    })
+                    .collect();
+                state
+                    .torrents
+                    .iter()
+                    .filter(|x| ids.contains(&x.hash) || ids.contains(&x.id.to_string()))
+                    .map(|x| x.hash.to_owned())
+                    .collect()
+            }
+            None => state.torrents.iter().map(|x| x.hash.to_owned()).collect(),
+        }
+    }
+
+    fn handle(request: &Value, state: &mut MockState) -> Value {
+        let method = request["method"].as_str().unwrap_or("");
+        let args = &request["arguments"];
+        let (result, arguments) = match method {
+            "session-get" => (
+                "success",
+                json!({
+                    "blocklist-enabled": false,
+                    "download-dir": "/downloads",
+                    "encryption": "preferred",
+                    "rpc-version": 15,
+                    "rpc-version-minimum": 1,
+                    "version": "3.00 (mock)",
+                }),
+            ),
+            "session-stats" => {
+                let active = state.torrents.iter().filter(|x| x.status != 0).count();
+                let stats = json!({
+                    "uploadedBytes": state.torrents.iter().map(|x| x.uploadedEver).sum::<i64>(),
+                    "downloadedBytes": 0,
+                    "filesAdded": state.torrents.len(),
+                    "sessionCount": 1,
+                    "secondsActive": 0,
+                });
+                (
+                    "success",
+                    json!({
+                        "activeTorrentCount": active,
+                        "pausedTorrentCount": state.torrents.len() - active,
+                        "torrentCount": state.torrents.len(),
+                        "downloadSpeed": 0,
+                        "uploadSpeed": 0,
+                        "cumulative-stats": stats,
+                        "current-stats": stats,
+                    }),
+                )
+            }
+            "free-space" => {
+                let path = args["path"].as_str().unwrap_or("").to_string();
+                state.freeSpacePaths.push(path);
+                (
+                    "success",
+                    json!({
+                        "path": args["path"],
+                        "size-bytes": state.freeSpace,
+                    }),
+                )
+            }
+            "torrent-get" => {
+                let hashes = FakeTransmission::selected(args, state);
+                let torrents: Vec<Value> = state
+                    .torrents
+                    .iter()
+                    .filter(|x| hashes.contains(&x.hash))
+                    .map(|x| x.toJsonFields(&args["fields"]))
+                    .collect();
+                ("success", json!({ "torrents": torrents }))
+            }
+            "torrent-add" => {
+                let name = args["filename"]
+                    .as_str()
+                    .or_else(|| args["metainfo"].as_str())
+                    .unwrap_or("")
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or("")
+                    .to_string();
+                let hash = MockTorrent::hashOf(&name);
+                if let Some(x) = state.torrents.iter().find(|x| x.hash == hash) {
+                    (
+                        "success",
+                        json!({
+                            "torrent-duplicate": {
+                                "id": x.id,
+                                "name": x.name,
+                                "hashString": x.hash,
+                            }
+                        }),
+                    )
+                } else {
+                    let mut torrent = MockTorrent::new(state.nextId, &name, 0, 0);
+                    state.nextId += 1;
+                    torrent.percentDone = 0.0;
+                    torrent.status = if args["paused"].as_bool().unwrap_or(false) {
+                        0
+                    } else {
+                        4
+                    };
+                    torrent.downloadDir = args["download-dir"].as_str().unwrap_or("").to_string();
+                    let added = json!({
+                        "torrent-added": {
+                            "id": torrent.id,
+                            "name": torrent.name,
+                            "hashString": torrent.hash,
+                        }
+                    });
+                    state.torrents.push(torrent);
+                    ("success", added)
+                }
+            }
+            "torrent-remove" => {
+                let hashes = FakeTransmission::selected(args, state);
+                let deleteData = args["delete-local-data"].as_bool().unwrap_or(false);
+                state.torrents.retain(|x| !hashes.contains(&x.hash));
+                for i in hashes.into_iter() {
+                    state.removed.push((i, deleteData));
+                }
+                ("success", json!({}))
+            }
+            "torrent-start" | "torrent-start-now" | "torrent-stop" | "torrent-verify"
+            | "torrent-reannounce" | "queue-move-top" | "queue-move-up" | "queue-move-down"
+            | "queue-move-bottom" => {
+                let hashes = FakeTransmission::selected(args, state);
+                for i in state.torrents.iter_mut() {
+                    if hashes.contains(&i.hash) {
+                        match method {
+                            "torrent-stop" => i.status = 0,
+                            "torrent-start" | "torrent-start-now" => {
+                                i.status = if i.percentDone >= 1.0 { 6 } else { 4 }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                for i in hashes.into_iter() {
+                    state.actions.push((method.to_string(), i));
+                }
+                ("success", json!({}))
+            }
+            _ => ("method name not recognized", json!({})),
+        };
+        json!({ "result": result, "arguments": arguments })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SiteState {
+    /// status codes answered in turn, 200 once used up
+    pub script: VecDeque<u16>,
+    pub hits: u32,
+    pub inFlight: u32,
+    pub maxInFlight: u32,
+}
+
+/// a web site answering every request after `delay` with the scripted status codes
+pub struct FakeSite {
+    url: String,
+    state: Arc<Mutex<SiteState>>,
+}
+
+impl FakeSite {
+    pub async fn start(script: &[u16], delay: Duration) -> Result<FakeSite> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(SiteState {
+            script: script.iter().cloned().collect(),
+            ..SiteState::default()
+        }));
+        let stateSep = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&stateSep);
+                tokio::spawn(async move {
+                    let _ = FakeSite::serve(stream, state, delay).await;
+                });
+            }
+        });
+        Ok(FakeSite { url, state })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn state(&self) -> std::sync::MutexGuard<'_, SiteState> {
+        self.state.lock().unwrap()
+    }
+
+    async fn serve(
+        mut stream: TcpStream,
+        state: Arc<Mutex<SiteState>>,
+        delay: Duration,
+    ) -> Result<()> {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        while !buf.windows(4).any(|x| x == b"\r\n\r\n") {
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        let code = {
+            let mut state = state.lock().unwrap();
+            state.hits += 1;
+            state.inFlight += 1;
+            state.maxInFlight = state.maxInFlight.max(state.inFlight);
+            state.script.pop_front().unwrap_or(200)
+        };
+        sleep(delay).await;
+        state.lock().unwrap().inFlight -= 1;
+        let body = code.to_string();
+        // redirects go to the login page, as the site does once the cookie expired
+        let location = if (300..400).contains(&code) {
+            "Location: /login.php\r\n"
+        } else {
+            ""
+        };
+        let response = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: text/plain\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            code,
+            location,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+}
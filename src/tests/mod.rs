@@ -1,4 +1,42 @@
+pub mod mock;
 pub mod test1;
+pub mod test10;
+pub mod test11;
+pub mod test12;
+pub mod test13;
+pub mod test14;
+pub mod test15;
+pub mod test16;
+pub mod test17;
+pub mod test18;
+pub mod test19;
 pub mod test2;
+pub mod test20;
 pub mod test3;
 pub mod test4;
+pub mod test5;
+pub mod test6;
+pub mod test7;
+pub mod test8;
+pub mod test9;
+
+use crate::u2client::types::Config;
+
+/// a `Config` with dummy required keys, `extra` is appended as toml
+pub fn config(extra: &str) -> Config {
+    tryConfig(extra).expect("wrong toml format")
+}
+
+/// as `config`, for keys meant to be refused
+pub fn tryConfig(extra: &str) -> std::result::Result<Config, toml::de::Error> {
+    let base = r#"
+cookie = ""
+workRoot = ""
+RpcURL = ""
+RpcUsername = ""
+RpcPassword = ""
+logRoot = ""
+maxSize = 100.0
+"#;
+    toml::from_str(&format!("{}{}", base, extra))
+}
@@ -1,6 +1,11 @@
+use crate::store::Store;
+use crate::tasks::removal::Removal;
 use crate::torrentLib::request::TorrentAction;
+use crate::u2client::cache::DetailCache;
 use crate::u2client::client::U2client;
+use crate::u2client::limiter::Limiter;
 use crate::{u2client, Result};
+use std::collections::HashSet;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -15,6 +20,8 @@ async fn test() -> Result<()> {
         &args.RpcUsername,
         &args.RpcPassword,
         &args.workRoot,
+        Limiter::new(&args),
+        DetailCache::new(&args),
     )
     .await?;
 
@@ -23,9 +30,9 @@ async fn test() -> Result<()> {
     let res = agent.getWorkingTorrent().await?;
     println!("{:?}\n", res);
 
-    let res = agent.getTorrent().await?;
+    let res = agent.getTorrent(&Default::default()).await?;
     for i in 0..4 {
-        let _ = agent.addTorrent(&res.get(i).unwrap().url).await?;
+        let _ = agent.addTorrent(&res.get(i).unwrap().url, false).await?;
         println!("{} added", i);
     }
     let res = agent.getWorkingTorrent().await?;
@@ -41,9 +48,11 @@ async fn test() -> Result<()> {
     }
     sleep(Duration::from_secs(5));
 
-    let x = agent.getRemove().await?;
+    let torrents = agent.getWorkingTorrent().await?.torrents;
+    let x =
+        Removal::new(&args)?.pick(&Store::open(":memory:")?, torrents, &HashSet::new(), 0, 0)?;
     for i in x {
-        let _ = agent.removeTorrent(i.hash_string.unwrap()).await?;
+        let _ = agent.removeTorrent(i.hash_string.unwrap(), true).await?;
     }
     let res = agent.getWorkingTorrent().await?;
     println!("{:?}\n", res);
@@ -0,0 +1,168 @@
+use crate::store::{AddedTorrent, MagicCast, RemovedTorrent, Store};
+use crate::tasks::ucoin;
+use crate::torrentLib::response::{SessionStats, Stats};
+use crate::u2client::types::{UCoinEntry, UserInfo};
+use crate::Result;
+
+#[test]
+fn added() -> Result<()> {
+    let store = Store::open(":memory:")?;
+    let x = AddedTorrent {
+        time: 100,
+        uid: "42".to_string(),
+        hash: "abc".to_string(),
+        title: "t".to_string(),
+        category: "BDMV".to_string(),
+        rule: "magic".to_string(),
+        magic: Some("HalfDown".to_string()),
+        cost: Some(12.5),
+    };
+    store.recordAdded(&x)?;
+    assert_eq!(store.added("abc")?, Some(x.clone()));
+    assert_eq!(store.added("def")?, None);
+    assert_eq!(store.addedSince(0)?, vec![x.clone()]);
+    assert!(store.addedSince(101)?.is_empty());
+    let categories = store.categories(&["abc", "def"])?;
+    assert_eq!(categories.len(), 1);
+    assert_eq!(categories["abc"], "BDMV");
+
+    let again = AddedTorrent {
+        time: 200,
+        magic: None,
+        cost: None,
+        ..x
+    };
+    store.recordAdded(&again)?;
+    assert_eq!(store.addedSince(0)?, vec![again]);
+    Ok(())
+}
+
+#[test]
+fn history() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("u2-store-{}.db", std::process::id()));
+    let path = path.to_str().ok_or("bad temp dir")?;
+    let _ = std::fs::remove_file(path);
+    let user = UserInfo {
+        username: "me".to_string(),
+        download: "1 TiB".to_string(),
+        upload: "2 TiB".to_string(),
+        shareRate: "2.000".to_string(),
+        actualDownload: "1 TiB".to_string(),
+        actualUpload: "2 TiB".to_string(),
+        coin: "1,000.5".to_string(),
+        downloadTime: "1d".to_string(),
+        uploadTime: "2d".to_string(),
+        timeRate: "2.000".to_string(),
+    };
+    let stats = Stats {
+        uploadedBytes: 300,
+        downloadedBytes: 100,
+        filesAdded: 0,
+        sessionCount: 1,
+        secondsActive: 0,
+    };
+    let session = SessionStats {
+        activeTorrentCount: 2,
+        downloadSpeed: 10,
+        pausedTorrentCount: 1,
+        torrentCount: 3,
+        uploadSpeed: 20,
+        cumulative_stats: stats.clone(),
+        current_stats: stats,
+    };
+    {
+        let store = Store::open(path)?;
+        store.recordUser(10, &user)?;
+        store.recordSession(10, &session)?;
+        store.recordRemoved(&RemovedTorrent {
+            time: 20,
+            hash: "abc".to_string(),
+            name: "t".to_string(),
+            size: 1 << 30,
+            reason: "maxSize".to_string(),
+        })?;
+    }
+
+    let store = Store::open(path)?;
+    let users = store.userSince(0)?;
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].0, 10);
+    assert_eq!(users[0].1.coinValue()?, 1000.5);
+    let sessions = store.sessionSince(0)?;
+    assert_eq!(sessions[0].uploaded, 300);
+    assert_eq!(sessions[0].uploadSpeed, 20);
+    assert!(store.sessionSince(11)?.is_empty());
+    assert_eq!(store.removedSince(0)?[0].size, 1 << 30);
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn ucoinLog() -> Result<()> {
+    let store = Store::open(":memory:")?;
+    let entry = |time: i64, amount: f64, reason: &str| UCoinEntry {
+        time,
+        amount,
+        reason: reason.to_string(),
+        torrent: None,
+    };
+    assert_eq!(store.ucoinLatest()?, None);
+    // two equal incomes within a second are two rows
+    let page = vec![
+        entry(30, -12.5, "魔法 free"),
+        entry(20, 3.0, "做种"),
+        entry(20, 3.0, "做种"),
+    ];
+    assert_eq!(store.recordUCoin(&page)?, 3);
+    assert_eq!(store.recordUCoin(&page[..2])?, 0);
+    let mut newer = vec![entry(40, 3.0, "做种")];
+    newer.extend(page);
+    assert_eq!(store.recordUCoin(&newer)?, 1);
+    assert_eq!(store.ucoinLatest()?, Some(40));
+
+    let entries = store.ucoinSince(0)?;
+    assert_eq!(entries.len(), 4);
+    assert_eq!(ucoin::magicSpend(&entries), vec![(30, 12.5)]);
+    let x = ucoin::summary(&entries, 25);
+    assert_eq!((x.income, x.magic, x.net), (3.0, 12.5, -9.5));
+    Ok(())
+}
+
+#[test]
+fn uploads() -> Result<()> {
+    let store = Store::open(":memory:")?;
+    store.recordUploads(0, &[("a", 1), ("b", 2)], 5)?;
+    // b is gone from the client
+    store.recordUploads(10, &[("a", 3)], 5)?;
+    store.recordUploads(20, &[("a", 6)], 5)?;
+    // the sample at 10 stays as the baseline of the window from 15
+    assert_eq!(
+        store.uploadsSince(15)?,
+        vec![(10, "a".to_string(), 3), (20, "a".to_string(), 6)]
+    );
+    assert_eq!(store.uploadsSince(0)?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn magic() -> Result<()> {
+    let store = Store::open(":memory:")?;
+    let x = MagicCast {
+        time: 100,
+        uid: "42".to_string(),
+        hash: "abc".to_string(),
+        title: "t".to_string(),
+        magic: "Free".to_string(),
+        cost: 12.5,
+    };
+    // kept even if the torrent never made it into `added`
+    store.recordMagic(&x)?;
+    store.recordMagic(&MagicCast {
+        time: 200,
+        ..x.clone()
+    })?;
+    assert!(store.addedSince(0)?.is_empty());
+    assert_eq!(store.magicSince(150)?, vec![MagicCast { time: 200, ..x }]);
+    assert_eq!(store.magicSince(0)?.len(), 2);
+    Ok(())
+}
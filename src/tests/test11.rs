@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use chrono::{Local, TimeZone};
+use futures::future::BoxFuture;
+use tokio::time::{sleep, Duration};
+
+use crate::scheduler::cron::Cron;
+use crate::scheduler::{Job, JobConfig, JobState, Schedule, Scheduler};
+use crate::Result;
+
+#[test]
+fn schedule() -> Result<()> {
+    assert_eq!(
+        Schedule::parse("90s")?,
+        Schedule::Every(Duration::from_secs(90))
+    );
+    assert_eq!(
+        Schedule::parse("5m")?,
+        Schedule::Every(Duration::from_secs(300))
+    );
+    assert_eq!(
+        Schedule::parse("2h")?,
+        Schedule::Every(Duration::from_secs(7200))
+    );
+    assert!(Schedule::parse("5 weeks").is_err());
+    assert!(Schedule::parse("5w").is_err());
+    assert_eq!(Schedule::parse("60s")?.next(100, None), Some(100));
+    assert_eq!(Schedule::parse("60s")?.next(100, Some(90)), Some(150));
+
+    let config = JobConfig {
+        schedule: Some("*/10 * * * *".to_string()),
+        timeout: Some(5),
+        ..JobConfig::default()
+    };
+    let job = Job::new("x", "60s", Some(&config))?;
+    assert_eq!(job.schedule, Schedule::Cron(Cron::parse("*/10 * * * *")?));
+    assert_eq!(job.timeout, Some(Duration::from_secs(5)));
+    assert!(Job::new("x", "soon", None).is_err());
+    Ok(())
+}
+
+#[test]
+fn cron() -> Result<()> {
+    let at = |y, m, d, h, min| Local.ymd(y, m, d).and_hms(h, min, 0).timestamp();
+    // a monday
+    let now = at(2021, 3, 1, 10, 7) + 30;
+
+    assert_eq!(
+        Cron::parse("*/15 * * * *")?.next(now),
+        Some(at(2021, 3, 1, 10, 15))
+    );
+    assert_eq!(
+        Cron::parse("0 0 * * *")?.next(now),
+        Some(at(2021, 3, 2, 0, 0))
+    );
+    assert_eq!(
+        Cron::parse("5,7 10 * * *")?.next(now),
+        Some(at(2021, 3, 2, 10, 5))
+    );
+    assert_eq!(
+        Cron::parse("30 9 * * 6-7")?.next(now),
+        Some(at(2021, 3, 6, 9, 30))
+    );
+    assert_eq!(
+        Cron::parse("30 9 * * 0")?.next(now),
+        Some(at(2021, 3, 7, 9, 30))
+    );
+    assert_eq!(
+        Cron::parse("0 0 29 2 *")?.next(now),
+        Some(at(2024, 2, 29, 0, 0))
+    );
+    // either the 13th or a friday
+    assert_eq!(
+        Cron::parse("0 0 13 * 5")?.next(now),
+        Some(at(2021, 3, 5, 0, 0))
+    );
+    assert_eq!(Cron::parse("0 0 30 2 *")?.next(now), None);
+
+    assert!(Cron::parse("60 * * * *").is_err());
+    assert!(Cron::parse("* * *").is_err());
+    assert!(Cron::parse("*/0 * * * *").is_err());
+    assert!(Cron::parse("5-1 * * * *").is_err());
+    Ok(())
+}
+
+struct Counter {
+    runs: AtomicUsize,
+}
+
+fn flaky(ctx: Arc<Counter>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        match ctx.runs.fetch_add(1, Ordering::SeqCst) {
+            0 => Err("flaky:first run fails".into()),
+            1 => panic!("second run panics"),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn stuck(_: Arc<Counter>) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        sleep(Duration::from_secs(60)).await;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn scheduler() -> Result<()> {
+    let ctx = Arc::new(Counter {
+        runs: AtomicUsize::new(0),
+    });
+    let mut scheduler = Scheduler::new(Arc::clone(&ctx));
+    scheduler.add(
+        Job {
+            name: "flaky".to_string(),
+            schedule: Schedule::parse("1h")?,
+            jitter: Duration::from_millis(0),
+            timeout: None,
+            retry: Some(Duration::from_secs(0)),
+        },
+        flaky,
+    );
+    scheduler.add(
+        Job {
+            name: "stuck".to_string(),
+            schedule: Schedule::parse("1h")?,
+            jitter: Duration::from_millis(10),
+            timeout: Some(Duration::from_millis(100)),
+            retry: None,
+        },
+        stuck,
+    );
+    let status = scheduler.status();
+    sleep(Duration::from_millis(500)).await;
+
+    let now = Local::now().timestamp();
+    let status = status.read().map_err(|_| "lock failed")?;
+    assert_eq!(ctx.runs.load(Ordering::SeqCst), 3);
+    assert_eq!(status[0].name, "flaky");
+    assert_eq!(status[0].state, JobState::Done);
+    assert_eq!((status[0].runs, status[0].failures), (3, 2));
+    assert!(status[0].nextRun.ok_or("no next run")? >= now + 3590);
+    assert_eq!(status[1].state, JobState::TimedOut);
+    assert_eq!((status[1].runs, status[1].failures), (1, 1));
+    assert!(status[1].lastRun.is_some());
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::time::Instant;
+
+use tokio::time::Duration;
+
+use crate::tests::mock::FakeSite;
+use crate::u2client::limiter::Limiter;
+use crate::Result;
+
+#[test]
+fn backoff() {
+    let limiter = Limiter::default();
+    for attempt in 0..4 {
+        let full = Duration::from_secs(1 << attempt);
+        let x = limiter.backoff(attempt);
+        assert!(x >= full / 2 && x <= full, "{:?} of attempt {}", x, attempt);
+    }
+    assert!(limiter.backoff(30) <= Duration::from_secs(120));
+}
+
+#[tokio::test]
+async fn retry() -> Result<()> {
+    let site = FakeSite::start(&[503], Duration::from_millis(0)).await?;
+    let client = reqwest::Client::new();
+    let limiter = Limiter::with(0.0, 1, 1, 1, Duration::from_secs(60));
+    let res = limiter.send(client.get(site.url())).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(site.state().hits, 2);
+    assert_eq!(limiter.pausedFor(), None);
+
+    // any other status is the caller's business and not a failure
+    let site = FakeSite::start(&[404], Duration::from_millis(0)).await?;
+    let res = limiter.send(client.get(site.url())).await?;
+    assert_eq!(res.status().as_u16(), 404);
+    assert_eq!(site.state().hits, 1);
+    assert_eq!(limiter.pausedFor(), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn circuit() -> Result<()> {
+    let site = FakeSite::start(&[500, 429, 502], Duration::from_millis(0)).await?;
+    let client = reqwest::Client::new();
+    let limiter = Limiter::with(0.0, 1, 0, 2, Duration::from_secs(60));
+    assert!(limiter.send(client.get(site.url())).await.is_err());
+    assert_eq!(limiter.pausedFor(), None);
+    assert!(limiter.send(client.get(site.url())).await.is_err());
+    assert!(limiter.pausedFor().unwrap() > Duration::from_secs(50));
+
+    // refused without touching the site while open
+    assert!(limiter.send(client.get(site.url())).await.is_err());
+    assert_eq!(site.state().hits, 2);
+
+    let limiter = Limiter::with(0.0, 1, 0, 1, Duration::from_millis(100));
+    assert!(limiter.send(client.get(site.url())).await.is_err());
+    assert!(limiter.pausedFor().is_some());
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let res = limiter.send(client.get(site.url())).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(limiter.pausedFor(), None);
+    assert_eq!(site.state().hits, 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn login() -> Result<()> {
+    let site = FakeSite::start(&[403, 302], Duration::from_millis(0)).await?;
+    let client = reqwest::Client::new();
+    let limiter = Limiter::with(0.0, 1, 3, 2, Duration::from_secs(60));
+    // not retried, and counted like a site that is down
+    assert!(limiter.send(client.get(site.url())).await.is_err());
+    assert_eq!(site.state().hits, 1);
+    assert_eq!(limiter.pausedFor(), None);
+    assert!(limiter.send(client.get(site.url())).await.is_err());
+    assert_eq!(site.state().hits, 3);
+    assert!(limiter.pausedFor().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn pacing() -> Result<()> {
+    let site = FakeSite::start(&[], Duration::from_millis(50)).await?;
+    let client = reqwest::Client::new();
+    let limiter = Limiter::with(20.0, 2, 0, 5, Duration::from_secs(60));
+    let start = Instant::now();
+    let res = futures::future::join_all((0..6).map(|_| limiter.send(client.get(site.url())))).await;
+    assert!(res.iter().all(|x| x.is_ok()));
+    // five gaps of 50ms between six starts
+    assert!(start.elapsed() >= Duration::from_millis(250));
+    let state = site.state();
+    assert_eq!(state.hits, 6);
+    assert!(state.maxInFlight <= 2);
+    Ok(())
+}
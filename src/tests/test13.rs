@@ -0,0 +1,75 @@
+use crate::tests::config;
+use crate::u2client::cache::{CacheStats, DetailCache};
+use crate::u2client::types::TorrentInfo;
+
+fn info(promotionExpire: Option<i64>) -> TorrentInfo {
+    TorrentInfo {
+        GbSize: 12.5,
+        uploadFX: 2.0,
+        downloadFX: 0.0,
+        promotionExpire,
+        seeder: 3,
+        leecher: 20,
+        avgProgress: 0.4,
+        Hash: "0123456789abcdef".to_string(),
+    }
+}
+
+#[test]
+fn ttl() {
+    let cache = DetailCache::with(3600, 300);
+    assert!(cache.get("1", 1000).is_none());
+    cache.put("1", &info(None), 1000);
+    assert_eq!(cache.get("1", 1299).map(|x| x.leecher), Some(20));
+    // peers are stale, the hash is not
+    assert!(cache.get("1", 1300).is_none());
+    assert_eq!(
+        cache.getStatic("1", 1300),
+        Some((12.5, "0123456789abcdef".to_string()))
+    );
+    // not a lookup of its own, details.php is asked next
+    assert!(cache.getStatic("1", 4600).is_none());
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 1,
+            staticHits: 1,
+            misses: 2
+        }
+    );
+    assert_eq!(cache.stats().saved(), 2);
+
+    // the promotion ending makes the fx stale
+    cache.put("2", &info(Some(1100)), 1000);
+    assert!(cache.get("2", 1050).is_some());
+    assert!(cache.get("2", 1100).is_none());
+    assert!(cache.getStatic("2", 1100).is_some());
+
+    // stale peers still do for a torrent already in the client
+    assert_eq!(cache.getAged("1", 1300).map(|x| x.leecher), Some(20));
+    assert!(cache.getAged("1", 4600).is_none());
+
+    // a magic cast changes the promotion
+    cache.invalidate("2");
+    assert!(cache.getStatic("2", 1050).is_none());
+
+    // whatever expired entirely goes on the next put
+    assert_eq!(cache.len(), 1);
+    cache.put("3", &info(None), 5000);
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn configured() {
+    let cache = DetailCache::new(&config("cacheStaticTtl = 600\ncacheVolatileTtl = 60\n"));
+    cache.put("1", &info(None), 0);
+    assert!(cache.get("1", 59).is_some());
+    assert!(cache.get("1", 60).is_none());
+    assert!(cache.getStatic("1", 599).is_some());
+    assert!(cache.getStatic("1", 600).is_none());
+
+    let cache = DetailCache::new(&config("cacheStaticTtl = 0\n"));
+    cache.put("1", &info(None), 0);
+    assert!(cache.is_empty());
+    assert!(cache.get("1", 0).is_none());
+}
@@ -0,0 +1,39 @@
+use std::io::Write;
+
+use crate::ui::sampler::{HardwareSampler, LogTail};
+use crate::Result;
+
+#[test]
+fn logTail() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("u2-log-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut tail = LogTail::new(path.to_str().ok_or("bad temp dir")?, 2);
+    assert!(tail.poll().is_err());
+
+    let mut file = std::fs::File::create(&path)?;
+    writeln!(file, "a\nb\nc")?;
+    file.flush()?;
+    assert_eq!(tail.poll()?, Some(vec!["c".to_string(), "b".to_string()]));
+    // unchanged, not read again
+    assert_eq!(tail.poll()?, None);
+
+    writeln!(file, "d")?;
+    file.flush()?;
+    assert_eq!(tail.poll()?, Some(vec!["d".to_string(), "c".to_string()]));
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn hardware() {
+    let mut sampler = HardwareSampler::new();
+    let first = sampler.sample();
+    let second = sampler.sample();
+    assert!(first.totalMemory > 0);
+    assert_eq!(first.totalMemory, second.totalMemory);
+    assert_eq!(first.cpu, second.cpu);
+    let mounts: Vec<&String> = second.disks.iter().map(|x| &x.mountPoint).collect();
+    let mut sorted = mounts.clone();
+    sorted.sort();
+    assert_eq!(mounts, sorted);
+}
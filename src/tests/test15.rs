@@ -0,0 +1,133 @@
+use crossterm::event::KeyCode;
+
+use crate::ui::torrents::{Column, TorrentRow, TorrentTable, TorrentView};
+use crate::ui::Cached;
+
+fn row(name: &str, size: i64, status: i64, category: Option<&str>) -> TorrentRow {
+    TorrentRow {
+        hash: format!("{:0>40}", name.len()),
+        name: name.to_string(),
+        size,
+        progress: 1.0,
+        status,
+        ratio: 0.0,
+        upload: 0,
+        download: 0,
+        peers: 0,
+        added: size,
+        fx: None,
+        category: category.map(|x| x.to_string()),
+    }
+}
+
+fn names(rows: &[TorrentRow]) -> Vec<&str> {
+    rows.iter().map(|x| x.name.as_str()).collect()
+}
+
+fn rows() -> Vec<TorrentRow> {
+    let mut ret = vec![
+        row("a", 3, 6, Some("BDMV")),
+        row("bb", 1, 0, Some("Music")),
+        row("ccc", 2, 4, None),
+        row("dddd", 4, 6, Some("BDMV")),
+    ];
+    ret[2].fx = Some((2.0, 0.0));
+    ret
+}
+
+#[test]
+fn sortAndFilter() {
+    let all = rows();
+    let mut view = TorrentView::default();
+    // newest first
+    assert_eq!(names(&view.apply(&all)), vec!["dddd", "a", "ccc", "bb"]);
+    view.sort = Column::Name;
+    view.descending = false;
+    assert_eq!(names(&view.apply(&all)), vec!["a", "bb", "ccc", "dddd"]);
+    view.sort = Column::FX;
+    view.descending = true;
+    assert_eq!(names(&view.apply(&all))[0], "ccc");
+
+    view.status = Some(6);
+    view.sort = Column::Size;
+    assert_eq!(names(&view.apply(&all)), vec!["dddd", "a"]);
+    view.status = None;
+    view.category = Some("BDMV".to_string());
+    view.text = "DD".to_string();
+    assert_eq!(names(&view.apply(&all)), vec!["dddd"]);
+
+    let table = TorrentTable::new(&all, &view);
+    assert_eq!(table.total, 4);
+    assert_eq!(table.rows.len(), 1);
+}
+
+#[test]
+fn keys() {
+    let all = rows();
+    let mut view = TorrentView::default();
+    view.sort = Column::Name;
+    view.descending = false;
+    let selected = |view: &TorrentView| {
+        names(&view.apply(&all))[view.position(&view.apply(&all)).unwrap()].to_string()
+    };
+    assert_eq!(selected(&view), "a");
+    assert!(view.handleKey(KeyCode::Down, &all));
+    assert!(view.handleKey(KeyCode::Down, &all));
+    assert_eq!(selected(&view), "ccc");
+    // the selection follows the torrent when the order changes
+    assert!(view.handleKey(KeyCode::Char('r'), &all));
+    assert_eq!(selected(&view), "ccc");
+    assert!(view.handleKey(KeyCode::End, &all));
+    assert_eq!(selected(&view), "a");
+    assert!(view.handleKey(KeyCode::PageUp, &all));
+    assert_eq!(selected(&view), "dddd");
+
+    assert!(view.handleKey(KeyCode::Char('s'), &all));
+    assert_eq!(view.sort, Column::Size);
+    assert!(view.handleKey(KeyCode::Char('S'), &all));
+    assert_eq!(view.sort, Column::Name);
+
+    // stopped, downloading, seeding, then every status again
+    for x in [Some(0), Some(4), Some(6), None].iter() {
+        assert!(view.handleKey(KeyCode::Char('f'), &all));
+        assert_eq!(view.status, *x);
+    }
+    assert!(view.handleKey(KeyCode::Char('c'), &all));
+    assert_eq!(view.category.as_deref(), Some("BDMV"));
+
+    // while searching q and the arrows are text, not commands
+    assert!(view.handleKey(KeyCode::Char('/'), &all));
+    assert!(view.handleKey(KeyCode::Char('q'), &all));
+    assert!(view.handleKey(KeyCode::Backspace, &all));
+    assert!(view.handleKey(KeyCode::Char('d'), &all));
+    assert!(!view.handleKey(KeyCode::Left, &all));
+    assert!(view.handleKey(KeyCode::Enter, &all));
+    assert_eq!(view.text, "d");
+    assert!(!view.handleKey(KeyCode::Char('q'), &all));
+
+    assert!(view.handleKey(KeyCode::Esc, &all));
+    assert_eq!(view.category, None);
+    assert!(view.text.is_empty());
+}
+
+#[test]
+fn cached() {
+    let all = vec![row("a", 1, 6, None), row("bb", 2, 6, None)];
+    let mut cache = Cached::new();
+    let mut view = TorrentView::default();
+    let mut builds = 0;
+    let mut get = |cache: &mut Cached<_, _>, generation: u64, view: &TorrentView| {
+        cache.get((generation, view.clone()), || {
+            builds += 1;
+            Some(TorrentTable::new(&all, view))
+        })
+    };
+    let first = get(&mut cache, 0, &view);
+    assert_eq!(get(&mut cache, 0, &view), first);
+    // new rows or another view rebuild it
+    get(&mut cache, 1, &view);
+    view.descending = false;
+    let x = get(&mut cache, 1, &view).expect("no table");
+    assert_eq!(x.rows[0].name, "a");
+    assert_eq!(builds, 3);
+}
@@ -0,0 +1,157 @@
+use crossterm::event::KeyCode;
+
+use crate::tests::mock::{FakeTransmission, MockTorrent};
+use crate::torrentLib::client::TransClient;
+use crate::torrentLib::request::TorrentGetField;
+use crate::torrentLib::response::{File, FileStat};
+use crate::u2client::client::U2client;
+use crate::ui::detail::{fileTree, FileNode};
+use crate::ui::torrents::{Column, TorrentRow, TorrentView};
+use crate::Result;
+
+fn file(name: &str, length: i64, done: i64) -> File {
+    File {
+        bytes_completed: done,
+        length,
+        name: name.to_string(),
+    }
+}
+
+fn stat(wanted: bool) -> FileStat {
+    FileStat {
+        bytes_completed: 0,
+        wanted,
+        priority: 0,
+    }
+}
+
+fn node(depth: usize, name: &str, length: i64, done: i64, wanted: bool, isDir: bool) -> FileNode {
+    FileNode {
+        depth,
+        name: name.to_string(),
+        length,
+        done,
+        wanted,
+        isDir,
+    }
+}
+
+#[test]
+fn tree() {
+    let files = vec![
+        file("A/BDMV/STREAM/00001.m2ts", 100, 50),
+        file("A/SCANS/01.png", 10, 0),
+        file("A/BDMV/index.bdmv", 1, 1),
+        file("A/SCANS/02.png", 10, 0),
+    ];
+    let stats = vec![stat(true), stat(false), stat(true), stat(false)];
+    assert_eq!(
+        fileTree(&files, &stats),
+        vec![
+            node(0, "A", 121, 51, true, true),
+            node(1, "BDMV", 101, 51, true, true),
+            node(2, "STREAM", 100, 50, true, true),
+            node(3, "00001.m2ts", 100, 50, true, false),
+            node(2, "index.bdmv", 1, 1, true, false),
+            node(1, "SCANS", 20, 0, false, true),
+            node(2, "01.png", 10, 0, false, false),
+            node(2, "02.png", 10, 0, false, false),
+        ]
+    );
+
+    // a single file torrent, without stats everything counts as wanted
+    let files = vec![file("a.mkv", 5, 5)];
+    assert_eq!(
+        fileTree(&files, &[]),
+        vec![node(0, "a.mkv", 5, 5, true, false)]
+    );
+}
+
+#[test]
+fn fields() {
+    let all = TorrentGetField::all();
+    let detail = TorrentGetField::detail();
+    assert!(all.iter().all(|x| !x.isDetail()));
+    assert_eq!(detail.len(), all.len() + 4);
+    assert_eq!(detail.iter().filter(|x| x.isDetail()).count(), 4);
+    assert!(TorrentGetField::Files.isDetail() && TorrentGetField::Trackerstats.isDetail());
+}
+
+#[test]
+fn pane() {
+    let row = |hash: &str| TorrentRow {
+        hash: hash.to_string(),
+        name: hash.to_string(),
+        size: 1,
+        progress: 1.0,
+        status: 6,
+        ratio: 0.0,
+        upload: 0,
+        download: 0,
+        peers: 0,
+        added: 0,
+        fx: None,
+        category: None,
+    };
+    let all = vec![row("a"), row("b")];
+    let mut view = TorrentView {
+        sort: Column::Name,
+        descending: false,
+        ..TorrentView::default()
+    };
+    assert!(!view.detail);
+    assert!(view.handleKey(KeyCode::Down, &all));
+    assert!(view.handleKey(KeyCode::Enter, &all));
+    assert!(view.detail);
+    assert_eq!(view.selected.as_deref(), Some("b"));
+
+    // the pane follows the selection, Esc closes it before clearing filters
+    assert!(view.handleKey(KeyCode::Up, &all));
+    assert!(view.detail);
+    assert_eq!(view.selected.as_deref(), Some("a"));
+    view.category = Some("BDMV".to_string());
+    assert!(view.handleKey(KeyCode::Esc, &all));
+    assert!(!view.detail);
+    assert_eq!(view.category.as_deref(), Some("BDMV"));
+    assert!(view.handleKey(KeyCode::Esc, &all));
+    assert_eq!(view.category, None);
+
+    // nothing to open in an empty list
+    assert!(view.handleKey(KeyCode::Enter, &[]));
+    assert!(!view.detail);
+}
+
+#[tokio::test]
+async fn fetch() -> Result<()> {
+    let mock = FakeTransmission::start().await?;
+    let root = std::env::temp_dir().join(format!("u2-detail-{}", std::process::id()));
+    std::fs::create_dir_all(&root)?;
+    let agent = U2client::withTransmission(
+        TransClient::new(mock.url()),
+        root.to_str().ok_or("bad temp dir")?,
+    )?;
+    let mut torrent = MockTorrent::new(1, "a", 200, 1);
+    torrent.files = vec![
+        ("a/1.m2ts".to_string(), 150, 150),
+        ("a/2.m2ts".to_string(), 50, 0),
+    ];
+    torrent.peers = vec![("10.0.0.1".to_string(), "qBittorrent 4.3.3".to_string())];
+    mock.push(torrent);
+    let hash = MockTorrent::hashOf("a");
+
+    let x = agent.getTorrentDetail(&hash).await?;
+    assert_eq!(x.hash_string.as_deref(), Some(hash.as_str()));
+    assert_eq!(x.files.as_ref().map(|x| x.len()), Some(2));
+    assert_eq!(x.file_stats.as_ref().map(|x| x.len()), Some(2));
+    assert_eq!(x.peers.as_ref().ok_or("no peers")?[0].address, "10.0.0.1");
+    let trackers = x.tracker_stats.ok_or("no tracker stats")?;
+    assert_eq!(trackers[0].host, "daydream.dmhy.best");
+    assert!(trackers[0].last_announce_succeeded);
+
+    // the list does not carry the detail fields
+    let list = agent.getWorkingTorrent().await?.torrents;
+    assert!(list[0].files.is_none() && list[0].peers.is_none());
+
+    assert!(agent.getTorrentDetail(&"0".repeat(40)).await.is_err());
+    Ok(())
+}
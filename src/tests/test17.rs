@@ -0,0 +1,110 @@
+use crossterm::event::KeyCode;
+
+use crate::tests::mock::{FakeTransmission, MockTorrent};
+use crate::torrentLib::client::TransClient;
+use crate::torrentLib::request::TorrentAction;
+use crate::u2client::client::U2client;
+use crate::ui::actions::{Command, Toast, TorrentCommand};
+use crate::ui::torrents::{TorrentRow, TorrentView};
+use crate::Result;
+
+fn row(hash: &str) -> TorrentRow {
+    TorrentRow {
+        hash: hash.to_string(),
+        name: hash.to_string(),
+        size: 1,
+        progress: 1.0,
+        status: 6,
+        ratio: 0.0,
+        upload: 0,
+        download: 0,
+        peers: 0,
+        added: 0,
+        fx: None,
+        category: None,
+    }
+}
+
+#[test]
+fn keys() {
+    let all = vec![row("a")];
+    let mut view = TorrentView::default();
+    assert!(view.handleKey(KeyCode::Char('p'), &all));
+    let x = view.command.take().expect("no command");
+    assert_eq!(x.hash, "a");
+    assert_eq!(x.command, Command::Action(TorrentAction::Stop));
+    assert!(view.handleKey(KeyCode::Char('T'), &all));
+    assert_eq!(
+        view.command.take().map(|x| x.command),
+        Some(Command::Action(TorrentAction::QueueTop))
+    );
+
+    // removing waits for a confirmation, other keys leave the dialog up
+    assert!(view.handleKey(KeyCode::Char('X'), &all));
+    assert_eq!(view.command, None);
+    assert_eq!(
+        view.confirm.as_ref().map(|x| x.command),
+        Some(Command::Remove(true))
+    );
+    assert!(view.handleKey(KeyCode::Char('q'), &all));
+    assert!(view.handleKey(KeyCode::Down, &all));
+    assert!(view.confirm.is_some());
+    assert!(view.handleKey(KeyCode::Esc, &all));
+    assert_eq!((view.confirm.as_ref(), view.command.as_ref()), (None, None));
+
+    assert!(view.handleKey(KeyCode::Char('x'), &all));
+    assert!(view.handleKey(KeyCode::Char('y'), &all));
+    assert_eq!(view.confirm, None);
+    assert_eq!(
+        view.command.take().map(|x| x.command),
+        Some(Command::Remove(false))
+    );
+
+    // nothing selected, nothing sent
+    assert!(view.handleKey(KeyCode::Char('t'), &[]));
+    assert_eq!(view.command, None);
+    assert!(!view.handleKey(KeyCode::Char('z'), &all));
+}
+
+#[test]
+fn describe() {
+    let x = TorrentCommand::new(&row("a"), Command::Remove(false));
+    assert_eq!(x.describe(), "remove a, keeping its data");
+    let x = TorrentCommand::new(&row("a"), Command::Action(TorrentAction::Reannounce));
+    assert_eq!(x.describe(), "reannounce a");
+    assert!(!Toast::new(x.describe(), true).expired());
+}
+
+#[tokio::test]
+async fn send() -> Result<()> {
+    let mock = FakeTransmission::start().await?;
+    let root = std::env::temp_dir().join(format!("u2-actions-{}", std::process::id()));
+    std::fs::create_dir_all(&root)?;
+    let agent = U2client::withTransmission(
+        TransClient::new(mock.url()),
+        root.to_str().ok_or("bad temp dir")?,
+    )?;
+    mock.push(MockTorrent::new(1, "a", 100, 1));
+    mock.push(MockTorrent::new(2, "b", 100, 2));
+    let (a, b) = (MockTorrent::hashOf("a"), MockTorrent::hashOf("b"));
+
+    agent
+        .performActionOnTorrent(a.to_owned(), TorrentAction::QueueBottom)
+        .await?;
+    agent
+        .performActionOnTorrent(a.to_owned(), TorrentAction::Verify)
+        .await?;
+    agent.removeTorrent(a.to_owned(), false).await?;
+    agent.removeTorrent(b.to_owned(), true).await?;
+    let state = mock.state();
+    assert_eq!(
+        state.actions,
+        vec![
+            ("queue-move-bottom".to_string(), a.to_owned()),
+            ("torrent-verify".to_string(), a.to_owned()),
+        ]
+    );
+    assert_eq!(state.removed, vec![(a, false), (b, true)]);
+    assert!(state.torrents.is_empty());
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crossterm::event::KeyCode;
+
+use crate::store::Store;
+use crate::tasks::promote::{addByHand, FeedEntry, Verdict};
+use crate::tasks::rules::magicHours;
+use crate::tests::config;
+use crate::tests::mock::{FakeTransmission, MockTorrent};
+use crate::torrentLib::client::TransClient;
+use crate::u2client::client::U2client;
+use crate::u2client::types::{RssInfo, TorrentInfo};
+use crate::ui::feed::{FeedAction, FeedView};
+use crate::Result;
+
+fn entry(uid: &str, verdict: Verdict) -> FeedEntry {
+    FeedEntry {
+        item: RssInfo {
+            title: format!("title {}", uid),
+            url: format!("https://u2.dmhy.org/download.php?id={}", uid),
+            cat: "BDMV".to_string(),
+            uid: uid.to_string(),
+            time: None,
+            U2Info: TorrentInfo {
+                GbSize: 20.0,
+                uploadFX: 2.0,
+                downloadFX: 0.0,
+                promotionExpire: None,
+                seeder: 1,
+                leecher: 10,
+                avgProgress: 0.1,
+                Hash: format!("{:0>40}", uid),
+            },
+        },
+        score: None,
+        verdict,
+    }
+}
+
+#[test]
+fn verdicts() {
+    assert_eq!(Verdict::Present.describe(), "in client");
+    assert_eq!(
+        Verdict::Skipped("by rule no rule".to_string()).describe(),
+        "skip, by rule no rule"
+    );
+    let x = Verdict::Added {
+        rule: "free".to_string(),
+        magic: Some(("Free".to_string(), 12.4)),
+        dryRun: false,
+    };
+    assert_eq!(x.describe(), "added by free, Free for 12 UCoin");
+    let x = Verdict::Added {
+        rule: "free".to_string(),
+        magic: None,
+        dryRun: true,
+    };
+    assert_eq!(x.describe(), "would add by free");
+    // within what the site allows
+    assert_eq!(magicHours(0.0), 24);
+    assert_eq!(magicHours(20.0), 24);
+    assert_eq!(magicHours(10000.0), 360);
+}
+
+#[test]
+fn keys() {
+    let entries = vec![
+        entry("1", Verdict::Present),
+        entry("2", Verdict::Skipped("too big".to_string())),
+    ];
+    let mut view = FeedView::default();
+    assert_eq!(view.position(&entries), Some(0));
+    assert!(view.handleKey(KeyCode::End, &entries));
+    assert_eq!(view.selected.as_deref(), Some("2"));
+    // magic spends UCoin, it waits for a confirmation
+    assert!(view.handleKey(KeyCode::Char('m'), &entries));
+    assert_eq!(view.command, None);
+    assert!(view.handleKey(KeyCode::Char('q'), &entries));
+    assert!(view.confirm.is_some());
+    assert!(view.handleKey(KeyCode::Esc, &entries));
+    assert_eq!((view.confirm.as_ref(), view.command.as_ref()), (None, None));
+    assert!(view.handleKey(KeyCode::Char('m'), &entries));
+    assert!(view.handleKey(KeyCode::Char('y'), &entries));
+    let x = view.command.take().expect("no command");
+    assert_eq!((x.item.uid.as_str(), x.action), ("2", FeedAction::Magic));
+    assert_eq!(x.describe(), "cast free on title 2");
+    assert!(view.handleKey(KeyCode::Char('a'), &entries));
+    assert_eq!(
+        view.command.take().map(|x| (x.item.uid, x.action)),
+        Some(("2".to_string(), FeedAction::Add))
+    );
+    // already in the client
+    assert!(view.handleKey(KeyCode::Up, &entries));
+    assert!(view.handleKey(KeyCode::Char('a'), &entries));
+    assert_eq!(view.command, None);
+
+    // gone from the feed, the first item is selected
+    assert_eq!(view.position(&entries[1..]), Some(0));
+    assert!(view.handleKey(KeyCode::Char('a'), &[]));
+    assert_eq!(view.command, None);
+    assert!(!view.handleKey(KeyCode::Char('q'), &entries));
+}
+
+#[tokio::test]
+async fn dryRun() -> Result<()> {
+    let mock = FakeTransmission::start().await?;
+    let root = std::env::temp_dir().join(format!("u2-feed-{}", std::process::id()));
+    std::fs::create_dir_all(&root)?;
+    let agent = U2client::withTransmission(
+        TransClient::new(mock.url()),
+        root.to_str().ok_or("bad temp dir")?,
+    )?;
+    let store = Store::open(":memory:")?;
+    let x = entry("1", Verdict::Skipped("too big".to_string()));
+    addByHand(&agent, &config("dryRun = true"), &store, &x.item).await?;
+    assert!(store.addedSince(0)?.is_empty());
+    assert!(mock.state().torrents.is_empty());
+
+    // refused whatever the feed said
+    let mut present = MockTorrent::new(1, "title 1", 1 << 30, 0);
+    present.hash = x.item.U2Info.Hash.to_owned();
+    mock.push(present);
+    assert!(addByHand(&agent, &config(""), &store, &x.item)
+        .await
+        .is_err());
+    assert!(store.addedSince(0)?.is_empty());
+    Ok(())
+}
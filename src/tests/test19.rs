@@ -0,0 +1,110 @@
+use crate::store::{SessionSample, Store};
+use crate::torrentLib::response::{SessionStats, Stats};
+use crate::u2client::types::UserInfo;
+use crate::ui::history::{History, RemoteSample, Window, RETAIN};
+use crate::Result;
+
+fn stats(uploadSpeed: u64, downloadSpeed: u64) -> SessionStats {
+    let zero = Stats {
+        uploadedBytes: 0,
+        downloadedBytes: 0,
+        filesAdded: 0,
+        sessionCount: 0,
+        secondsActive: 0,
+    };
+    SessionStats {
+        activeTorrentCount: 1,
+        downloadSpeed,
+        pausedTorrentCount: 0,
+        torrentCount: 1,
+        uploadSpeed,
+        cumulative_stats: zero.clone(),
+        current_stats: zero,
+    }
+}
+
+fn user(upload: &str, download: &str, shareRate: &str) -> UserInfo {
+    UserInfo {
+        username: "u".to_string(),
+        download: download.to_string(),
+        upload: upload.to_string(),
+        shareRate: shareRate.to_string(),
+        actualDownload: download.to_string(),
+        actualUpload: upload.to_string(),
+        coin: "1,000.00".to_string(),
+        downloadTime: "1天 00:00:00".to_string(),
+        uploadTime: "2天 00:00:00".to_string(),
+        timeRate: "2.000".to_string(),
+    }
+}
+
+#[test]
+fn remote() {
+    let x = RemoteSample::new(1, &user("2 TiB", "512 GiB", "4.000")).expect("no sample");
+    assert_eq!((x.upload, x.download, x.ratio), (2048.0, 512.0, 4.0));
+    assert_eq!(RemoteSample::new(1, &user("2 TiB", "0 B", "---")), None);
+    assert_eq!(Window::Week.next(), Window::FiveMinutes);
+    assert_eq!(Window::default().next().seconds(), 3600);
+}
+
+#[test]
+fn memory() {
+    let mut history = History::new();
+    let now = 10 * RETAIN;
+    for i in 0..=RETAIN / 2 {
+        let time = now - RETAIN - 100 + i * 2;
+        history.pushSession(time, &stats(i as u64, 0));
+    }
+    let x = history.graph(Window::FiveMinutes, now, &[], &[]);
+    // one sample every 2s, one bucket every 2s
+    assert!(!x.upload.is_empty() && x.upload.len() <= 120);
+    assert!(x.upload.iter().all(|y| y.0 > 0.0 && y.0 <= 300.0));
+    assert!(x.upload.windows(2).all(|y| y[0].1 < y[1].1));
+    assert!(x.ratio.is_empty());
+
+    // older than the hour kept is gone
+    let x = history.graph(Window::Day, now, &[], &[]);
+    let oldest = x.upload.first().expect("no points");
+    assert!(oldest.0 >= (86400 - RETAIN) as f64);
+}
+
+#[test]
+fn stored() -> Result<()> {
+    let store = Store::open(":memory:")?;
+    let now = 1_000_000;
+    for i in 1..=12 {
+        let time = now - 86400 + i * 3600;
+        store.recordSession(time, &stats(1000, 10))?;
+        let upload = format!("{} GiB", 100 + i);
+        store.recordUser(time, &user(&upload, "10 GiB", &format!("{}", 10 + i)))?;
+    }
+    let mut history = History::new();
+    // memory wins over the snapshots once it starts
+    history.pushSession(now - 1800, &stats(5000, 0));
+    history.pushRemote(now - 1800, &user("200 GiB", "10 GiB", "20"));
+
+    let session: Vec<SessionSample> = store.sessionSince(now - 86400)?;
+    let user = store.userSince(now - 86400)?;
+    let x = history.graph(Window::Day, now, &session, &user);
+    assert_eq!(x.upload.len(), 13);
+    assert_eq!(x.upload.last().map(|y| y.1), Some(5000.0));
+    assert!(x.upload[..12].iter().all(|y| y.1 == 1000.0));
+    // gained since the first sample in the window
+    assert_eq!(x.remoteUpload.first().map(|y| y.1), Some(0.0));
+    assert_eq!(x.remoteUpload.last().map(|y| y.1), Some(99.0));
+    assert_eq!(x.remoteDownload.last().map(|y| y.1), Some(0.0));
+    assert_eq!(x.ratio.last().map(|y| y.1), Some(20.0));
+
+    let x = history.graph(Window::Week, now, &[], &[]);
+    assert_eq!(x.upload.len(), 1);
+    assert_eq!(x.window, Window::Week);
+
+    // right after a restart the hour is filled from the snapshots too
+    assert_eq!(history.coveredSince(), Some(now - 1800));
+    store.recordSession(now - 600, &stats(3000, 0))?;
+    let session = store.sessionSince(now - 3600)?;
+    let x = History::new().graph(Window::Hour, now, &session, &[]);
+    assert_eq!(x.upload, vec![(3000.0, 3000.0)]);
+    assert_eq!(History::new().coveredSince(), None);
+    Ok(())
+}
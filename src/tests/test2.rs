@@ -1,4 +1,6 @@
+use crate::u2client::cache::DetailCache;
 use crate::u2client::client::U2client;
+use crate::u2client::limiter::Limiter;
 use crate::{u2client, Result};
 
 #[tokio::test]
@@ -12,6 +14,8 @@ async fn test() -> Result<()> {
         &args.RpcUsername,
         &args.RpcPassword,
         &args.workRoot,
+        Limiter::new(&args),
+        DetailCache::new(&args),
     )
     .await?;
 
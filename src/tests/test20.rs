@@ -0,0 +1,130 @@
+use std::io::Write;
+
+use crossterm::event::KeyCode;
+use log::Level;
+
+use crate::ui::log::{LogLine, LogTable, LogView};
+use crate::ui::sampler::LogTail;
+use crate::Result;
+
+#[test]
+fn parse() {
+    let x = LogLine::parse("2021-03-01 12:00:00 ERROR - backEnd:get U2 Info failed");
+    assert_eq!(x.time, "2021-03-01 12:00:00");
+    assert_eq!(x.level, Level::Error);
+    assert_eq!(x.task.as_deref(), Some("backEnd"));
+    assert_eq!(x.text, "backEnd:get U2 Info failed");
+
+    // written before the level was logged
+    let x = LogLine::parse("2021-03-01 12:00:00 - apply magic on:a, 10 UCoin");
+    assert_eq!(
+        (x.time.as_str(), x.level),
+        ("2021-03-01 12:00:00", Level::Info)
+    );
+    assert_eq!(x.task, None);
+    let x = LogLine::parse("init done");
+    assert_eq!((x.time.as_str(), x.text.as_str()), ("", "init done"));
+}
+
+/// newest first, as `LogTail` gives them
+fn lines() -> Vec<LogLine> {
+    [
+        "2021-03-01 12:00:05 INFO - promote:done",
+        "2021-03-01 12:00:04 WARN - maintain:Disk almost full",
+        "2021-03-01 12:00:03 ERROR - backEnd:get U2 Info failed",
+        "2021-03-01 12:00:02 INFO - maintain:remove a, 1 GB",
+        "2021-03-01 12:00:01 INFO - promote:skip b, full disk",
+    ]
+    .iter()
+    .map(|x| LogLine::parse(x))
+    .collect()
+}
+
+#[test]
+fn filters() {
+    let all = lines();
+    let mut view = LogView::default();
+    assert_eq!(view.apply(&all).len(), 5);
+    assert!(view.handleKey(KeyCode::Char('l'), &all));
+    assert_eq!(view.level, Some(Level::Error));
+    assert_eq!(view.apply(&all).len(), 1);
+    assert!(view.handleKey(KeyCode::Char('l'), &all));
+    assert_eq!(view.apply(&all).len(), 2);
+
+    // tasks in name order
+    assert!(view.handleKey(KeyCode::Char('t'), &all));
+    assert_eq!(view.task.as_deref(), Some("backEnd"));
+    assert!(view.handleKey(KeyCode::Char('t'), &all));
+    assert_eq!(view.task.as_deref(), Some("maintain"));
+    let table = LogTable::new(&all, &view);
+    assert_eq!((table.shown, table.total), (1, 5));
+    assert_eq!(table.lines[0].text, "maintain:Disk almost full");
+
+    assert!(view.handleKey(KeyCode::Esc, &all));
+    assert_eq!(view, LogView::default());
+}
+
+#[test]
+fn scrollAndSearch() {
+    let all = lines();
+    let mut view = LogView::default();
+    assert!(view.handleKey(KeyCode::PageDown, &all));
+    assert_eq!(view.offset, 4);
+    assert!(view.handleKey(KeyCode::Up, &all));
+    assert_eq!(LogTable::new(&all, &view).lines.len(), 2);
+    assert!(view.handleKey(KeyCode::Home, &all));
+    assert_eq!(view.offset, 0);
+    assert!(view.handleKey(KeyCode::End, &all));
+    assert_eq!(view.offset, 4);
+
+    // keys go to the search until Enter
+    assert!(view.handleKey(KeyCode::Char('/'), &all));
+    for x in "DISK".chars() {
+        assert!(view.handleKey(KeyCode::Char(x), &all));
+    }
+    assert!(view.handleKey(KeyCode::Enter, &all));
+    assert_eq!(view.search, "DISK");
+    assert!(view.handleKey(KeyCode::Char('n'), &all));
+    assert_eq!(view.offset, 1);
+    assert!(view.handleKey(KeyCode::Char('n'), &all));
+    assert_eq!(view.offset, 4);
+    assert!(view.handleKey(KeyCode::Char('n'), &all));
+    assert_eq!(view.offset, 1);
+
+    // an offset past the filtered lines is pulled back
+    view.level = Some(Level::Error);
+    assert_eq!(LogTable::new(&all, &view).view.offset, 0);
+    assert!(!view.handleKey(KeyCode::Char('q'), &all));
+}
+
+#[test]
+fn archives() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("u2-logs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let main = dir.join("main.log");
+    let archive = dir.join("0.log");
+    let _ = std::fs::remove_file(&archive);
+    std::fs::write(&main, "a\nb\n")?;
+    let archives = vec![archive.to_str().ok_or("bad temp dir")?.to_string()];
+    let mut tail = LogTail::withArchives(main.to_str().ok_or("bad temp dir")?, &archives, 3);
+    assert_eq!(tail.poll()?, Some(vec!["b".to_string(), "a".to_string()]));
+    assert_eq!(tail.poll()?, None);
+
+    // rolled over, the old lines now come from the archive
+    std::fs::rename(&main, &archive)?;
+    let mut file = std::fs::File::create(&main)?;
+    writeln!(file, "c")?;
+    file.flush()?;
+    assert_eq!(
+        tail.poll()?,
+        Some(vec!["c".to_string(), "b".to_string(), "a".to_string()])
+    );
+    writeln!(file, "d")?;
+    file.flush()?;
+    assert_eq!(
+        tail.poll()?,
+        Some(vec!["d".to_string(), "c".to_string(), "b".to_string()])
+    );
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
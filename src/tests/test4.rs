@@ -1,4 +1,7 @@
+use crate::u2client::cache::DetailCache;
 use crate::u2client::client::U2client;
+use crate::u2client::limiter::Limiter;
+use crate::u2client::types::{Magic, MagicRequest, MagicTarget};
 use crate::{u2client, Result};
 
 #[tokio::test]
@@ -12,8 +15,15 @@ async fn test() -> Result<()> {
         &args.RpcUsername,
         &args.RpcPassword,
         &args.workRoot,
+        Limiter::new(&args),
+        DetailCache::new(&args),
     )
     .await?;
-    let _ = agent.applyMagic("234", 24, 5).await;
+    let request = MagicRequest {
+        magic: Magic::HalfDown,
+        target: MagicTarget::Own,
+        hours: 24,
+    };
+    let _ = agent.applyMagic("234", &request).await;
     Ok(())
 }
@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+
+use crate::store::{AddedTorrent, Store};
+use crate::tasks::maintain;
+use crate::tasks::removal::Removal;
+use crate::tasks::scope::Scope;
+use crate::tests::config;
+use crate::tests::mock::{FakeTransmission, MockTorrent};
+use crate::torrentLib::client::TransClient;
+use crate::torrentLib::request::{Id, TorrentAction, TorrentAddArgs};
+use crate::u2client::client::U2client;
+use crate::Result;
+
+async fn setUp(name: &str) -> Result<(FakeTransmission, U2client)> {
+    let mock = FakeTransmission::start().await?;
+    let root = std::env::temp_dir().join(format!("u2-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&root)?;
+    let agent = U2client::withTransmission(
+        TransClient::new(mock.url()),
+        root.to_str().ok_or("bad temp dir")?,
+    )?;
+    Ok((mock, agent))
+}
+
+#[tokio::test]
+async fn handshake() -> Result<()> {
+    let (mock, agent) = setUp("handshake").await?;
+    let session = agent.getTransmissionSession().await?;
+    assert_eq!(session.rpc_version, 15);
+    assert!(mock.state().handshakes >= 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn addAndRemove() -> Result<()> {
+    let mock = FakeTransmission::start().await?;
+    let client = TransClient::new(mock.url());
+    let add = TorrentAddArgs {
+        filename: Some("/tmp/a.torrent".to_string()),
+        paused: Some(true),
+        ..TorrentAddArgs::default()
+    };
+    let added = client.torrent_add(add).await?;
+    assert!(added.is_ok());
+    let hash = added
+        .arguments
+        .torrent_added
+        .ok_or("torrent not added")?
+        .hash_string
+        .ok_or("no hash")?;
+
+    let res = client.torrent_get(None, None).await?.arguments.torrents;
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].status, Some(0));
+
+    client
+        .torrent_action(TorrentAction::Start, vec![Id::Hash(hash.to_owned())])
+        .await?;
+    let res = client.torrent_get(None, None).await?.arguments.torrents;
+    assert_eq!(res[0].status, Some(4));
+
+    client
+        .torrent_remove(vec![Id::Hash(hash.to_owned())], true)
+        .await?;
+    assert!(client
+        .torrent_get(None, None)
+        .await?
+        .arguments
+        .torrents
+        .is_empty());
+    assert_eq!(mock.state().removed, vec![(hash, true)]);
+    Ok(())
+}
+
+async fn pick(
+    agent: &U2client,
+    removal: &Removal,
+    protected: &HashSet<String>,
+    excess: i64,
+) -> Result<Vec<String>> {
+    let torrents = agent.getWorkingTorrent().await?.torrents;
+    Ok(removal
+        .pick(&Store::open(":memory:")?, torrents, protected, excess, 1000)?
+        .into_iter()
+        .map(|x| x.name.unwrap())
+        .collect())
+}
+
+async fn pickAt(
+    agent: &U2client,
+    removal: &Removal,
+    store: &Store,
+    now: i64,
+) -> Result<Vec<String>> {
+    let torrents = agent.getWorkingTorrent().await?.torrents;
+    Ok(removal
+        .pick(store, torrents, &HashSet::new(), 0, now)?
+        .into_iter()
+        .map(|x| x.name.unwrap())
+        .collect())
+}
+
+#[tokio::test]
+async fn removal() -> Result<()> {
+    let (mock, agent) = setUp("removal").await?;
+    for i in 1..=7 {
+        let mut x = MockTorrent::new(i, &format!("t{}", i), 1_000_000_000, 100 - i);
+        x.peersGettingFromUs = if i % 2 == 0 { 3 } else { 0 };
+        mock.push(x);
+    }
+    let removal = Removal::new(&config(""))?;
+    let res = pick(&agent, &removal, &HashSet::new(), 0).await?;
+    assert_eq!(res, vec!["t7", "t5", "t3", "t1", "t6"]);
+
+    let protected = vec![MockTorrent::hashOf("t7"), MockTorrent::hashOf("t3")]
+        .into_iter()
+        .collect();
+    let res = pick(&agent, &removal, &protected, 0).await?;
+    assert_eq!(res, vec!["t5", "t1", "t6", "t4", "t2"]);
+
+    let enough = Removal::new(&config("removeMode = \"enough\"\n"))?;
+    let res = pick(&agent, &enough, &HashSet::new(), 1_500_000_000).await?;
+    assert_eq!(res, vec!["t7", "t5"]);
+    assert!(pick(&agent, &enough, &HashSet::new(), 0).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn removalStrategies() -> Result<()> {
+    let (mock, agent) = setUp("removalStrategies").await?;
+    for i in 1..=4 {
+        let mut x = MockTorrent::new(i, &format!("t{}", i), i * 1_000_000_000, 0);
+        x.activityDate = 10 - i;
+        x.doneDate = if i == 2 { 0 } else { i };
+        mock.push(x);
+    }
+    let strategy = |x: &str| Removal::new(&config(&format!("removeStrategy = [{}]\n", x)));
+    let all = HashSet::new();
+    assert_eq!(
+        pick(&agent, &strategy("\"largest\"")?, &all, 0).await?,
+        vec!["t4", "t3", "t2", "t1"]
+    );
+    assert_eq!(
+        pick(&agent, &strategy("\"leastActive\"")?, &all, 0).await?,
+        vec!["t4", "t3", "t2", "t1"]
+    );
+    assert_eq!(
+        pick(&agent, &strategy("\"oldestDone\"")?, &all, 0).await?,
+        vec!["t1", "t3", "t4", "t2"]
+    );
+
+    // counted from the last sample before the window, which starts at day 1.5 by default
+    let day = 86400;
+    let store = Store::open(":memory:")?;
+    let uploaded = strategy("\"leastUploaded\", \"largest\"")?;
+    let upload = |x: [i64; 4]| {
+        for (i, y) in mock.state().torrents.iter_mut().zip(x.iter()) {
+            i.uploadedEver = *y;
+        }
+    };
+    upload([9_000_000_000, 1_000_000_000, 0, 0]);
+    let torrents = agent.getWorkingTorrent().await?.torrents;
+    uploaded.record(&store, day, &torrents)?;
+    upload([9_000_000_000, 2_000_000_000, 3_000_000_000, 0]);
+    let torrents = agent.getWorkingTorrent().await?.torrents;
+    uploaded.record(&store, 2 * day, &torrents)?;
+    upload([9_500_000_000, 6_000_000_000, 3_000_000_000, 0]);
+    assert_eq!(store.uploadsSince(day + day / 2)?.len(), 8);
+    // t1 uploaded most overall but only 0.5 GB since day 1
+    let now = 8 * day + day / 2;
+    assert_eq!(
+        pickAt(&agent, &uploaded, &store, now).await?,
+        vec!["t4", "t1", "t3", "t2"]
+    );
+    let ratio = strategy("\"ratioGain\"")?;
+    assert_eq!(
+        pickAt(&agent, &ratio, &store, now).await?,
+        vec!["t4", "t3", "t1", "t2"]
+    );
+    // once day 2 is the last sample before the window, only 0.5 GB counts for t1 and 4 GB for t2
+    assert_eq!(
+        pickAt(&agent, &uploaded, &store, now + day).await?,
+        vec!["t4", "t3", "t1", "t2"]
+    );
+
+    assert!(strategy("\"nope\"").is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn maintainSize() -> Result<()> {
+    let (mock, agent) = setUp("maintainSize").await?;
+    for i in 1..=8 {
+        mock.push(MockTorrent::new(i, &format!("t{}", i), 2_000_000_000, i));
+    }
+    let mut args = config("");
+    let removal = Removal::new(&args)?;
+    let store = Store::open(":memory:")?;
+    let scope = Scope::new(&args)?;
+    args.maxSize = 20.0;
+    maintain::handleOne(&agent, &args, &removal, &store, &scope).await?;
+    assert!(mock.state().removed.is_empty());
+
+    args.maxSize = 10.0;
+    args.dryRun = Some(true);
+    maintain::handleOne(&agent, &args, &removal, &store, &scope).await?;
+    assert!(mock.state().removed.is_empty());
+    assert_eq!(mock.state().torrents.len(), 8);
+    assert!(store.removedSince(0)?.is_empty());
+
+    args.dryRun = None;
+    maintain::handleOne(&agent, &args, &removal, &store, &scope).await?;
+    let state = mock.state();
+    let left: Vec<&str> = state.torrents.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(left, vec!["t6", "t7", "t8"]);
+    assert!(state.removed.iter().all(|x| x.1));
+    assert!(state.actions.iter().all(|x| x.0 == "torrent-reannounce"));
+    let removed = store.removedSince(0)?;
+    assert_eq!(removed.len(), 5);
+    assert_eq!(removed[0].name, "t1");
+    assert!(removed.iter().all(|x| x.reason.contains("maxSize")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn maintainWatermark() -> Result<()> {
+    let (mock, agent) = setUp("maintainWatermark").await?;
+    for i in 1..=8 {
+        mock.push(MockTorrent::new(i, &format!("t{}", i), 1 << 30, i));
+    }
+    assert_eq!(agent.getDiskFree().await?, 1 << 40);
+    // the disk new torrents go to, not the session default
+    let paths = mock.state().freeSpacePaths.clone();
+    assert!(!paths.is_empty() && paths.iter().all(|x| x.ends_with("/work")));
+    mock.state().freeSpace = 5 << 30;
+    let args = config("lowWatermark = 4.0\nhighWatermark = 8.0\nremoveMode = \"enough\"\n");
+    let removal = Removal::new(&args)?;
+    let store = Store::open(":memory:")?;
+    let scope = Scope::new(&args)?;
+    maintain::handleOne(&agent, &args, &removal, &store, &scope).await?;
+    assert!(mock.state().removed.is_empty());
+
+    mock.state().freeSpace = 3 << 30;
+    maintain::handleOne(&agent, &args, &removal, &store, &scope).await?;
+    let left: Vec<String> = mock
+        .state()
+        .torrents
+        .iter()
+        .map(|x| x.name.to_owned())
+        .collect();
+    assert_eq!(left, vec!["t6", "t7", "t8"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn maintainScope() -> Result<()> {
+    let (mock, agent) = setUp("maintainScope").await?;
+    for i in 1..=4 {
+        mock.push(MockTorrent::new(i, &format!("t{}", i), 2_000_000_000, i));
+    }
+    let mut own = MockTorrent::new(5, "personal", 2_000_000_000, 0);
+    own.trackers = vec!["udp://tracker.example.org:1337/announce".to_string()];
+    mock.push(own);
+    let args = config("denyList = [\"^t1$\"]\nremoveMode = \"enough\"\n");
+    let store = Store::open(":memory:")?;
+    let removal = Removal::new(&args)?;
+    let scope = Scope::new(&args)?;
+    let mut small = args.clone();
+    small.maxSize = 1.0;
+    maintain::handleOne(&agent, &small, &removal, &store, &scope).await?;
+    let state = mock.state();
+    let left: Vec<&str> = state.torrents.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(left, vec!["t1", "personal"]);
+    assert!(state
+        .actions
+        .iter()
+        .all(|x| x.1 != MockTorrent::hashOf("t1") && x.1 != MockTorrent::hashOf("personal")));
+    drop(state);
+
+    let added = Scope::new(&config("manage = \"added\"\n"))?;
+    let torrents = agent.getWorkingTorrent().await?.torrents;
+    assert!(added.filter(torrents.clone(), &store)?.is_empty());
+    store.recordAdded(&AddedTorrent {
+        time: 0,
+        uid: "1".to_string(),
+        hash: MockTorrent::hashOf("t1"),
+        title: "t1".to_string(),
+        category: String::new(),
+        rule: "default".to_string(),
+        magic: None,
+        cost: None,
+    })?;
+    assert_eq!(added.filter(torrents.clone(), &store)?.len(), 1);
+    let all = Scope::new(&config("manage = \"all\"\nallowList = [\"nothing\"]\n"))?;
+    assert_eq!(all.filter(torrents, &store)?.len(), 2);
+    assert!(Scope::new(&config("manage = \"mine\"\n")).is_err());
+    Ok(())
+}
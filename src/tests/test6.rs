@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use crate::store::{AddedTorrent, Store};
+use crate::tasks::expire::{self, ExpireAction};
+use crate::tasks::maintain;
+use crate::tests::mock::{FakeTransmission, MockTorrent};
+use crate::tests::{config, tryConfig};
+use crate::torrentLib::client::TransClient;
+use crate::u2client::client::U2client;
+use crate::u2client::types::{Magic, MagicRequest, MagicTarget, SeedingRecord};
+use crate::Result;
+
+const LIST: &str = r#"<html><body><table class="torrents">
+<tr><td class="colhead">类型</td><td class="colhead">标题</td><td class="colhead">评论</td><td class="colhead">时间</td><td class="colhead">大小</td><td class="colhead">做种</td><td class="colhead">下载</td><td class="colhead">完成</td></tr>
+<tr>
+<td class="rowfollow"><a href="?cat=12"><img class="category" alt="BDMV" src="pic/cat.png"></a></td>
+<td class="rowfollow"><table class="torrentname"><tr><td class="embedded"><a title="foo" href="details.php?id=42704&amp;hit=1"><b>[BDMV] foo</b></a><img class="pro_free" alt="FREE" src="pic/trans.gif"></td></tr></table></td>
+<td class="rowfollow">0</td>
+<td class="rowfollow"><span title="2021-03-20 12:00:00">1天</span></td>
+<td class="rowfollow">41.2<br>GiB</td>
+<td class="rowfollow">1,024</td>
+<td class="rowfollow">7</td>
+<td class="rowfollow">33</td>
+</tr>
+<tr>
+<td class="rowfollow"><a href="?cat=9"><img class="category" alt="Music" src="pic/cat.png"></a></td>
+<td class="rowfollow"><table class="torrentname"><tr><td class="embedded"><a title="bar" href="details.php?id=7&amp;hit=1"><b>bar</b></a></td></tr></table></td>
+<td class="rowfollow">2</td>
+<td class="rowfollow"><span title="2021-03-19 12:00:00">2天</span></td>
+<td class="rowfollow">300<br>MiB</td>
+<td class="rowfollow">0</td>
+<td class="rowfollow">0</td>
+<td class="rowfollow">1</td>
+</tr>
+</table></body></html>"#;
+
+#[test]
+fn parseTorrentList() -> Result<()> {
+    let res = U2client::parseTorrentList(LIST)?;
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].uid, "42704");
+    assert_eq!(res[0].title, "[BDMV] foo");
+    assert_eq!(res[0].cat, "BDMV");
+    assert_eq!((res[0].seeder, res[0].leecher), (1024, 7));
+    assert!(res[0].time.is_some());
+    assert_eq!(res[1].uid, "7");
+    assert_eq!((res[1].seeder, res[1].leecher), (0, 0));
+    Ok(())
+}
+
+const PEERS: &str = r#"<b>1 个做种者</b>
+<table><tr><td class="colhead">用户</td><td class="colhead">可连接</td><td class="colhead">上传</td><td class="colhead">速率</td><td class="colhead">下载</td><td class="colhead">速率</td><td class="colhead">分享率</td><td class="colhead">完成</td><td class="colhead">连接时间</td><td class="colhead">最近汇报</td><td class="colhead">客户端</td></tr>
+<tr><td class="rowfollow">匿名</td><td class="rowfollow">是</td><td class="rowfollow">1.50 TiB</td><td class="rowfollow">0 B/s</td><td class="rowfollow">0.00 B</td><td class="rowfollow">0 B/s</td><td class="rowfollow">Inf.</td><td class="rowfollow">100%</td><td class="rowfollow">10天</td><td class="rowfollow">1分钟</td><td class="rowfollow">qBittorrent/4.3.3</td></tr>
+</table>
+<b>2 个下载者</b>
+<table><tr><td class="colhead">用户</td><td class="colhead">可连接</td><td class="colhead">上传</td><td class="colhead">速率</td><td class="colhead">下载</td><td class="colhead">速率</td><td class="colhead">分享率</td><td class="colhead">完成</td><td class="colhead">连接时间</td><td class="colhead">最近汇报</td><td class="colhead">客户端</td></tr>
+<tr><td class="rowfollow">a</td><td class="rowfollow">否</td><td class="rowfollow">0.00 B</td><td class="rowfollow">0 B/s</td><td class="rowfollow">512.00 MiB</td><td class="rowfollow">1 MiB/s</td><td class="rowfollow">0</td><td class="rowfollow">12.5%</td><td class="rowfollow">1时</td><td class="rowfollow">1分钟</td><td class="rowfollow">Transmission/3.00</td></tr>
+<tr><td class="rowfollow">b</td><td class="rowfollow">是</td><td class="rowfollow">1.00 GiB</td><td class="rowfollow">0 B/s</td><td class="rowfollow">2.00 GiB</td><td class="rowfollow">1 MiB/s</td><td class="rowfollow">0.5</td><td class="rowfollow">60%</td><td class="rowfollow">1时</td><td class="rowfollow">1分钟</td><td class="rowfollow">Deluge 2.0.3</td></tr>
+</table>"#;
+
+#[test]
+fn parsePeerList() -> Result<()> {
+    let res = U2client::parsePeerList(PEERS)?;
+    assert_eq!(res.len(), 3);
+    assert!(res[0].connectable);
+    assert_eq!(res[0].uploaded, 1.5 * 1024.0);
+    assert_eq!(res[0].progress, 1.0);
+    assert!(!res[1].connectable);
+    assert_eq!(res[1].downloaded, 0.5);
+    assert_eq!(res[1].progress, 0.125);
+    assert_eq!(res[1].client, "Transmission/3.00");
+    assert_eq!(res[2].uploaded, 1.0);
+    Ok(())
+}
+
+#[test]
+fn parseSitePromotion() -> Result<()> {
+    let page = r#"<html><body><table><tr><td>全站 2X Free 中, 结束于 2021-03-20 12:00:00</td></tr></table></body></html>"#;
+    let res = U2client::parseSitePromotion(page)?.ok_or("no promotion")?;
+    assert_eq!((res.uploadFX, res.downloadFX), (2.0, 0.0));
+    assert!(res.expire.is_some());
+    assert!(U2client::parseSitePromotion(LIST)?.is_none());
+    Ok(())
+}
+
+#[test]
+fn magicForm() -> Result<()> {
+    let request = MagicRequest {
+        magic: Magic::Custom {
+            upload: 2.0,
+            download: 0.3,
+        },
+        target: MagicTarget::User("foo".to_string()),
+        hours: 360,
+    };
+    let form = U2client::magicForm("42", &request)?;
+    let get = |k: &str| form.iter().find(|x| x.0 == k).map(|x| x.1.as_str());
+    assert_eq!(get("hours"), Some("360"));
+    assert_eq!(get("promotion"), Some("8"));
+    assert_eq!(get("user"), Some("OTHER"));
+    assert_eq!(get("user_other"), Some("foo"));
+    assert_eq!(get("ur"), Some("2.00"));
+    assert_eq!(get("dr"), Some("0.30"));
+
+    let request = MagicRequest {
+        magic: Magic::Custom {
+            upload: 3.0,
+            download: 0.3,
+        },
+        target: MagicTarget::All,
+        hours: 1,
+    };
+    assert!(U2client::magicForm("42", &request).is_err());
+
+    // hours outside the site's limits are refused, not clamped
+    for hours in [2, 23, 361, 1000] {
+        let request = MagicRequest {
+            magic: Magic::Free,
+            target: MagicTarget::Own,
+            hours,
+        };
+        assert!(U2client::magicForm("42", &request).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn parseMagicReply() {
+    let reply = |heading: &str, text: &str| {
+        format!(
+            "<html><body><table><tr><td><a href=\"index.php\">操作成功后返回首页</a></td></tr></table>\
+             <table class=\"main\"><tr><td class=\"embedded\"><h2>{}</h2>\
+             <table><tr><td class=\"text\">{}</td></tr></table></td></tr></table></body></html>",
+            heading, text
+        )
+    };
+    assert!(U2client::parseMagicReply(&reply("成功", "魔法已生效")).is_ok());
+    // 成功 elsewhere on the page is not the result
+    let failed = U2client::parseMagicReply(&reply("错误", "UCoin不足")).unwrap_err();
+    assert_eq!(failed.to_string(), "apply magic failed:UCoin不足");
+    assert!(U2client::parseMagicReply("<html><body>成功</body></html>").is_err());
+}
+
+#[test]
+fn parseUCoinLog() -> Result<()> {
+    let page = r#"<table><tr><td class="colhead">时间</td><td class="colhead">数额</td><td class="colhead">原因</td></tr>
+<tr><td><time title="2021-03-20 12:00:00">1天前</time></td><td>-1,234.50</td><td>施加魔法 <a href="details.php?id=42704">foo</a></td></tr>
+<tr><td><time title="2021-03-20 13:00:00">1天前</time></td><td>+88.8</td><td>做种奖励</td></tr>
+</table>"#;
+    let res = U2client::parseUCoinLog(page)?;
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].amount, -1234.5);
+    assert!(res[0].isMagic());
+    assert_eq!(res[0].torrent.as_deref(), Some("42704"));
+    assert_eq!(res[1].amount, 88.8);
+    assert_eq!(res[1].time - res[0].time, 3600);
+    assert_eq!(res[1].torrent, None);
+    Ok(())
+}
+
+#[test]
+fn parseSeedingRecords() -> Result<()> {
+    let page = r#"<table><tr><td class="colhead">类型</td><td class="colhead">标题</td><td class="colhead">上传</td><td class="colhead">下载</td><td class="colhead">分享率</td><td class="colhead">做种时间</td><td class="colhead">完成时间</td></tr>
+<tr><td>BDMV</td><td><a href="details.php?id=42704&amp;hit=1"><b>foo</b></a></td><td>20.00 GiB</td><td>40.00 GiB</td><td>0.500</td><td>1天 02:03:04</td><td>2021-03-20 12:00:00</td></tr>
+<tr><td>Music</td><td><a href="details.php?id=7&amp;hit=1"><b>bar</b></a></td><td>1.00 GiB</td><td>0.00 B</td><td>Inf.</td><td>00:10:00</td><td>2021-03-20 12:00:00</td></tr>
+</table>"#;
+    let res = U2client::parseSeedingRecords(page)?;
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].uid, "42704");
+    assert_eq!((res[0].uploaded, res[0].downloaded), (20.0, 40.0));
+    assert_eq!(res[0].ratio, 0.5);
+    assert_eq!(res[0].seedTime, 86400 + 2 * 3600 + 3 * 60 + 4);
+    assert_eq!(res[1].ratio, f32::INFINITY);
+    assert_eq!(res[1].seedTime, 600);
+    Ok(())
+}
+
+#[tokio::test]
+async fn obligations() -> Result<()> {
+    let mock = FakeTransmission::start().await?;
+    let root = std::env::temp_dir().join(format!("u2-obligations-{}", std::process::id()));
+    std::fs::create_dir_all(&root)?;
+    let agent = U2client::withTransmission(
+        TransClient::new(mock.url()),
+        root.to_str().ok_or("bad temp dir")?,
+    )?;
+    let store = Store::open(":memory:")?;
+    store.recordAdded(&AddedTorrent {
+        time: 0,
+        uid: "42704".to_string(),
+        hash: "aa".to_string(),
+        title: "foo".to_string(),
+        category: "BDMV".to_string(),
+        rule: "default".to_string(),
+        magic: None,
+        cost: None,
+    })?;
+    // known from the store after a restart
+    assert_eq!(agent.uidOf("aa", &store)?.as_deref(), Some("42704"));
+    assert_eq!(agent.lookupUid("aa").as_deref(), Some("42704"));
+    assert_eq!(agent.uidOf("bb", &store)?, None);
+
+    let record = |uid: &str, ratio: f32, seedTime: i64| SeedingRecord {
+        uid: uid.to_string(),
+        uploaded: 0.0,
+        downloaded: 0.0,
+        ratio,
+        seedTime,
+    };
+    let records = vec![
+        record("42704", 0.5, 3600),
+        record("1", 0.5, 3600),
+        record("2", 2.0, 3600),
+    ];
+    let uids: HashMap<String, String> = [("42704", "aa"), ("2", "cc")]
+        .iter()
+        .map(|(x, y)| (x.to_string(), y.to_string()))
+        .collect();
+    let args = config("minSeedTime = 24.0\nminSeedRatio = 1.0\n");
+    // not in the client, or seeded enough
+    let kept = maintain::unmet(&args, &records, &uids);
+    assert_eq!(kept.into_iter().collect::<Vec<_>>(), vec!["aa".to_string()]);
+
+    // added by hand, its U2 id is unknown so it is kept; torrents of other trackers owe nothing
+    let mut ours = MockTorrent::new(1, "foo", 1 << 30, 0);
+    ours.hash = "aa".to_string();
+    mock.push(ours);
+    let mut byHand = MockTorrent::new(2, "bar", 1 << 30, 0);
+    byHand.trackers = vec!["https://daydream.dmhy.best/announce?secure=x".to_string()];
+    mock.push(byHand);
+    let mut other = MockTorrent::new(3, "baz", 1 << 30, 0);
+    other.trackers = vec!["udp://tracker.example.org:1337/announce".to_string()];
+    mock.push(other);
+    let working = agent.getWorkingTorrent().await?.torrents;
+    let (uids, unknown) = maintain::resolve(&agent, &store, &working);
+    assert_eq!(uids.get("42704").map(|x| x.as_str()), Some("aa"));
+    assert_eq!(uids.len(), 1);
+    assert_eq!(
+        unknown.into_iter().collect::<Vec<_>>(),
+        vec![MockTorrent::hashOf("bar")]
+    );
+    Ok(())
+}
+
+#[test]
+fn expireAction() {
+    assert_eq!(
+        config("expireAction = \"remove\"\n").expireAction,
+        Some(ExpireAction::Remove)
+    );
+    assert_eq!(ExpireAction::Pause.to_string(), "pause");
+    // a typo no longer falls back to pausing
+    assert!(tryConfig("expireAction = \"pasue\"\n").is_err());
+}
+
+#[test]
+fn expireOverruns() {
+    let now = 1_000_000;
+    let margin = 30 * 60;
+    // finishes in time, or not
+    assert!(!expire::overruns(now, Some(3600), margin, now + 86400));
+    assert!(expire::overruns(now, Some(86400), margin, now + 3600));
+    // no eta and a window far away, nothing to do yet
+    assert!(!expire::overruns(now, Some(-1), margin, now + 3 * 86400));
+    // no eta and the window about to close
+    assert!(expire::overruns(now, Some(-1), margin, now + 60));
+    assert!(!expire::overruns(now, None, margin, now + 3 * 86400));
+    // no end known
+    assert!(!expire::overruns(now, Some(-1), margin, i64::MAX));
+}
@@ -0,0 +1,49 @@
+use crate::tasks::budget::{BudgetPeriod, MagicBudget};
+use crate::tests::{config, tryConfig};
+
+#[test]
+fn budget() {
+    let mut budget = MagicBudget::new(&config("magicBudget = 1000.0\n"));
+    assert!(budget.reserve(0, 600.0));
+    assert!(!budget.reserve(10, 600.0));
+    assert_eq!(budget.remaining(10), Some(400.0));
+    budget.refund(0, 600.0);
+    assert_eq!(budget.remaining(10), Some(1000.0));
+
+    assert!(budget.reserve(100, 900.0));
+    assert!(!budget.reserve(200, 200.0));
+    assert!(budget.reserve(86400 + 100, 200.0));
+
+    let mut budget = MagicBudget::new(&config(
+        "magicBudget = 1000.0\nmagicBudgetPeriod = \"weekly\"\n",
+    ));
+    assert!(budget.reserve(0, 900.0));
+    assert!(!budget.reserve(86400 * 3, 200.0));
+    assert!(budget.reserve(86400 * 7, 200.0));
+
+    assert_eq!(
+        config("magicBudgetPeriod = \"daily\"\n").magicBudgetPeriod,
+        Some(BudgetPeriod::Daily)
+    );
+    // a typo no longer falls back to daily
+    assert!(tryConfig("magicBudgetPeriod = \"weekley\"\n").is_err());
+}
+
+#[test]
+fn balance() {
+    let mut budget = MagicBudget::new(&config(""));
+    assert_eq!(budget.remaining(0), None);
+    budget.setBalance(500.0);
+    assert!(budget.reserve(0, 300.0));
+    assert!(!budget.reserve(0, 300.0));
+}
+
+#[test]
+fn sync() {
+    let mut budget = MagicBudget::new(&config("magicBudget = 1000.0\n"));
+    assert!(budget.reserve(100, 300.0));
+    assert!(budget.reserve(200, 300.0));
+    budget.sync(150, vec![(90, 300.0), (-86400, 5000.0)]);
+    assert_eq!(budget.remaining(200), Some(400.0));
+    assert_eq!(budget.remaining(86400 + 95), Some(700.0));
+}
@@ -0,0 +1,159 @@
+use crate::tasks::rules::{Decision, RuleEngine};
+use crate::tests::{config, tryConfig};
+use crate::u2client::types::{Magic, RssInfo, TorrentInfo};
+
+fn item(title: &str, cat: &str, GbSize: f32, downloadFX: f32, seeder: i32) -> RssInfo {
+    RssInfo {
+        title: title.to_string(),
+        url: String::new(),
+        cat: cat.to_string(),
+        uid: "1".to_string(),
+        time: Some(0),
+        U2Info: TorrentInfo {
+            GbSize,
+            uploadFX: 1.0,
+            downloadFX,
+            promotionExpire: None,
+            seeder,
+            leecher: 1,
+            avgProgress: 0.1,
+            Hash: String::new(),
+        },
+    }
+}
+
+#[test]
+fn defaults() {
+    let rules = RuleEngine::new(&config("downloadFxFilter = 0.0\nGBSizeFilter = 1.0\n")).unwrap();
+
+    let (name, decision) = rules.decide(&item("a", "BDMV", 10.0, 1.0, 0), 0, false);
+    assert_eq!(name, "no seeder");
+    assert!(matches!(decision, Decision::Skip));
+
+    let mut popular = item("a", "BDMV", 10.0, 1.0, 3);
+    popular.U2Info.avgProgress = 0.8;
+    assert_eq!(rules.decide(&popular, 0, false).0, "popular");
+    // hungry torrents are joined anyway, only the popular rule asks
+    assert!(rules.asksHungry(&popular, 0));
+    assert_eq!(rules.decide(&popular, 0, true).0, "magic");
+    assert!(!rules.asksHungry(&item("a", "BDMV", 10.0, 1.0, 3), 0));
+    // the bound is exclusive, as the filter always was
+    popular.U2Info.avgProgress = 0.5;
+    assert_eq!(rules.decide(&popular, 0, false).0, "magic");
+
+    match rules.decide(&item("a", "BDMV", 10.0, 1.0, 3), 0, false) {
+        (name, Decision::Add { paused, magic }) => {
+            assert_eq!(name, "magic");
+            assert!(!paused);
+            let magic = magic.unwrap();
+            assert_eq!(magic.magic, Magic::HalfDown);
+            assert_eq!(magic.hours, 24);
+        }
+        x => panic!("unexpected {:?}", x),
+    }
+
+    for x in [
+        item("a", "BDMV", 10.0, 0.0, 3),
+        item("a", "BDMV", 1.0, 1.0, 3),
+    ]
+    .iter()
+    {
+        match rules.decide(x, 0, false) {
+            (name, Decision::Add { magic: None, .. }) => assert_eq!(name, "default"),
+            x => panic!("unexpected {:?}", x),
+        }
+    }
+}
+
+#[test]
+fn configured() {
+    let rules = r#"
+[[rules]]
+name = "fresh music"
+category = ["Music"]
+maxAge = 1.0
+action = "add"
+
+[[rules]]
+name = "anime"
+category = ["BDMV", "DVDISO"]
+title = "(?i)remux"
+action = "addPaused"
+priority = 5
+
+[[rules]]
+name = "big"
+minSize = 50.0
+action = "magic"
+magic = { Custom = { upload = 2.0, download = 0.5 } }
+hours = 48
+priority = 10
+"#;
+    let first = RuleEngine::new(&config(rules)).unwrap();
+    let now = 3600 * 12;
+
+    match first.decide(&item("Foo REMUX", "BDMV", 60.0, 1.0, 3), now, false) {
+        (name, Decision::Add { paused: true, .. }) => assert_eq!(name, "anime"),
+        x => panic!("unexpected {:?}", x),
+    }
+    assert_eq!(
+        first
+            .decide(&item("Foo", "BDMV", 60.0, 1.0, 3), now, false)
+            .0,
+        "big"
+    );
+    assert_eq!(
+        first
+            .decide(&item("Foo", "Music", 1.0, 1.0, 3), now, false)
+            .0,
+        "no rule"
+    );
+    let mut fresh = item("Foo", "Music", 1.0, 1.0, 3);
+    fresh.time = Some(now - 60);
+    assert_eq!(first.decide(&fresh, now, false).0, "fresh music");
+    fresh.time = None;
+    assert_eq!(first.decide(&fresh, now, false).0, "no rule");
+
+    let priority =
+        RuleEngine::new(&config(&format!("ruleMode = \"priority\"\n{}", rules))).unwrap();
+    match priority.decide(&item("Foo REMUX", "BDMV", 60.0, 1.0, 3), now, false) {
+        (
+            name,
+            Decision::Add {
+                magic: Some(magic), ..
+            },
+        ) => {
+            assert_eq!(name, "big");
+            assert_eq!(magic.hours, 48);
+            assert_eq!(
+                magic.magic,
+                Magic::Custom {
+                    upload: 2.0,
+                    download: 0.5
+                }
+            );
+        }
+        x => panic!("unexpected {:?}", x),
+    }
+}
+
+#[test]
+fn invalid() {
+    let noMagic = "[[rules]]\nname = \"x\"\naction = \"magic\"\n";
+    assert!(RuleEngine::new(&config(noMagic)).is_err());
+    let badTitle = "[[rules]]\nname = \"x\"\ntitle = \"(\"\naction = \"add\"\n";
+    assert!(RuleEngine::new(&config(badTitle)).is_err());
+    let badHours = "[[rules]]\nname = \"x\"\naction = \"magic\"\nmagic = \"Free\"\nhours = 2\n";
+    assert!(RuleEngine::new(&config(badHours)).is_err());
+    // a typo in the mode fails at load instead of falling back to first match
+    assert!(tryConfig("ruleMode = \"Priority\"\n").is_err());
+    // the lowest priority sorts last instead of overflowing
+    let lowest = "[[rules]]\nname = \"x\"\naction = \"add\"\npriority = -2147483648\n\n\
+                  [[rules]]\nname = \"y\"\naction = \"skip\"\n";
+    let lowest = config(&format!("ruleMode = \"priority\"\n{}", lowest));
+    let engine = RuleEngine::new(&lowest).unwrap();
+    assert_eq!(
+        engine.decide(&item("Foo", "BDMV", 1.0, 1.0, 3), 0, false).0,
+        "y"
+    );
+}
@@ -0,0 +1,104 @@
+use crate::tasks::score::{Candidate, Scorer};
+use crate::tests::config;
+use crate::u2client::types::{RssInfo, TorrentInfo};
+
+fn item(GbSize: f32, downloadFX: f32, seeder: i32, leecher: i32) -> RssInfo {
+    RssInfo {
+        title: String::new(),
+        url: String::new(),
+        cat: String::new(),
+        uid: String::new(),
+        time: None,
+        U2Info: TorrentInfo {
+            GbSize,
+            uploadFX: 1.0,
+            downloadFX,
+            promotionExpire: None,
+            seeder,
+            leecher,
+            avgProgress: 0.0,
+            Hash: String::new(),
+        },
+    }
+}
+
+#[test]
+fn score() {
+    let scorer = Scorer::new(&config(""));
+    let (hot, reason) = scorer.score(&item(10.0, 0.0, 1, 10));
+    let (cold, _) = scorer.score(&item(10.0, 0.0, 10, 1));
+    let (paid, _) = scorer.score(&item(10.0, 1.0, 1, 10));
+    assert!(hot > cold && hot > paid);
+    assert!(reason.contains("demand 5.00*1.00"));
+
+    let scorer = Scorer::new(&config("[scoreWeights]\ndemand = 0.0\nfree = 0.0\n"));
+    assert_eq!(
+        scorer.score(&item(10.0, 0.0, 1, 10)).0,
+        scorer.score(&item(10.0, 1.0, 10, 1)).0
+    );
+}
+
+#[test]
+fn select() {
+    let candidates = || {
+        vec![
+            Candidate {
+                score: 1.0,
+                GbSize: 10.0,
+                item: 1,
+            },
+            Candidate {
+                score: 3.0,
+                GbSize: 30.0,
+                item: 3,
+            },
+            Candidate {
+                score: 2.0,
+                GbSize: 20.0,
+                item: 2,
+            },
+        ]
+    };
+    let taken = |scorer: &Scorer, pending, free| -> Vec<i32> {
+        let mut selection = scorer.selection(pending, free);
+        let mut ret = Vec::new();
+        for x in scorer.rank(candidates()).into_iter() {
+            if selection.check(x.GbSize).is_none() {
+                selection.take(x.GbSize);
+                ret.push(x.item);
+            }
+        }
+        ret
+    };
+
+    assert_eq!(taken(&Scorer::new(&config("")), 0.0, None), vec![3, 2, 1]);
+    assert_eq!(
+        taken(&Scorer::new(&config("maxAddPerCycle = 2\n")), 0.0, None),
+        vec![3, 2]
+    );
+    assert_eq!(
+        taken(&Scorer::new(&config("maxAddSize = 45.0\n")), 0.0, None),
+        vec![3, 1]
+    );
+    let pending = Scorer::new(&config("maxPendingSize = 50.0\n"));
+    assert_eq!(taken(&pending, 0.0, None), vec![3, 2]);
+    assert_eq!(taken(&pending, 25.0, None), vec![2]);
+
+    let disk = Scorer::new(&config("lowWatermark = 10.0\n"));
+    assert_eq!(taken(&disk, 0.0, None), vec![3, 2, 1]);
+    assert_eq!(taken(&disk, 0.0, Some(50.0)), vec![3, 1]);
+    assert_eq!(taken(&disk, 15.0, Some(50.0)), vec![2]);
+
+    // a candidate left out by the caller keeps its slot free for the next one
+    let top = Scorer::new(&config("maxAddPerCycle = 2\n"));
+    let mut selection = top.selection(0.0, None);
+    let ranked = top.rank(candidates());
+    assert_eq!(ranked[0].item, 3);
+    selection.take(ranked[1].GbSize);
+    assert!(selection.check(ranked[2].GbSize).is_none());
+    selection.take(ranked[2].GbSize);
+    assert_eq!(
+        selection.check(ranked[0].GbSize),
+        Some("not in the top 2".to_string())
+    );
+}
@@ -20,6 +20,13 @@ pub struct TransClient {
 }
 
 impl TransClient {
+    pub fn new(url: &str) -> TransClient {
+        TransClient {
+            url: url.to_string(),
+            auth: None,
+        }
+    }
+
     pub fn with_auth(url: &str, basic_auth: BasicAuth) -> TransClient {
         TransClient {
             url: url.to_string(),
@@ -94,7 +94,8 @@ pub struct TorrentGetArgs {
 
 impl Default for TorrentGetArgs {
     fn default() -> Self {
-        let all_fields = TorrentGetField::into_enum_iter()
+        let all_fields = TorrentGetField::all()
+            .iter()
             .map(|it| it.to_str())
             .collect();
         TorrentGetArgs {
@@ -157,7 +158,9 @@ impl Default for TorrentAddArgs {
 #[derive(Clone, IntoEnumIterator)]
 pub enum TorrentGetField {
     Id,
+    Activitydate,
     Addeddate,
+    Donedate,
     Name,
     HashString,
     Totalsize,
@@ -185,19 +188,43 @@ pub enum TorrentGetField {
     Uploadedever,
     Uploadratio,
     Webseedssendingtous,
+    Files,
+    Filestats,
+    Peers,
+    Trackerstats,
 }
 
 impl TorrentGetField {
+    /// every field but the per file, peer and tracker lists, which are large
     pub fn all() -> Vec<TorrentGetField> {
+        TorrentGetField::into_enum_iter()
+            .filter(|x| !x.isDetail())
+            .collect()
+    }
+
+    /// every field, for a few torrents at a time
+    pub fn detail() -> Vec<TorrentGetField> {
         TorrentGetField::into_enum_iter().collect()
     }
+
+    pub fn isDetail(&self) -> bool {
+        matches!(
+            self,
+            TorrentGetField::Files
+                | TorrentGetField::Filestats
+                | TorrentGetField::Peers
+                | TorrentGetField::Trackerstats
+        )
+    }
 }
 
 impl TorrentGetField {
     pub fn to_str(&self) -> String {
         match self {
             TorrentGetField::Id => "id",
+            TorrentGetField::Activitydate => "activityDate",
             TorrentGetField::Addeddate => "addedDate",
+            TorrentGetField::Donedate => "doneDate",
             TorrentGetField::Name => "name",
             TorrentGetField::HashString => "hashString",
             TorrentGetField::Totalsize => "totalSize",
@@ -225,17 +252,26 @@ impl TorrentGetField {
             TorrentGetField::Uploadedever => "uploadedEver",
             TorrentGetField::Uploadratio => "uploadRatio",
             TorrentGetField::Webseedssendingtous => "webseedsSendingToUs",
+            TorrentGetField::Files => "files",
+            TorrentGetField::Filestats => "fileStats",
+            TorrentGetField::Peers => "peers",
+            TorrentGetField::Trackerstats => "trackerStats",
         }
         .to_string()
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TorrentAction {
     Start,
     Stop,
     StartNow,
     Verify,
     Reannounce,
+    QueueTop,
+    QueueUp,
+    QueueDown,
+    QueueBottom,
 }
 
 impl TorrentAction {
@@ -246,6 +282,10 @@ impl TorrentAction {
             TorrentAction::StartNow => "torrent-start-now",
             TorrentAction::Verify => "torrent-verify",
             TorrentAction::Reannounce => "torrent-reannounce",
+            TorrentAction::QueueTop => "queue-move-top",
+            TorrentAction::QueueUp => "queue-move-up",
+            TorrentAction::QueueDown => "queue-move-down",
+            TorrentAction::QueueBottom => "queue-move-bottom",
         }
         .to_string()
     }
@@ -26,10 +26,14 @@ pub struct SessionGet {
     pub version: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct Torrent {
+    #[serde(rename = "activityDate")]
+    pub activity_date: Option<i64>,
     #[serde(rename = "addedDate")]
     pub added_date: Option<i64>,
+    #[serde(rename = "doneDate")]
+    pub done_date: Option<i64>,
     #[serde(rename = "downloadDir")]
     pub download_dir: Option<String>,
     pub error: Option<i64>,
@@ -74,6 +78,12 @@ pub struct Torrent {
     pub upload_ratio: Option<f32>,
     #[serde(rename = "uploadedEver")]
     pub uploaded_ever: Option<i64>,
+    pub files: Option<Vec<File>>,
+    #[serde(rename = "fileStats")]
+    pub file_stats: Option<Vec<FileStat>>,
+    pub peers: Option<Vec<Peer>>,
+    #[serde(rename = "trackerStats")]
+    pub tracker_stats: Option<Vec<TrackerStat>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -81,12 +91,61 @@ pub struct Torrents<T> {
     pub torrents: Vec<T>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct Trackers {
     pub id: i32,
     pub announce: String,
 }
 
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct File {
+    #[serde(rename = "bytesCompleted")]
+    pub bytes_completed: i64,
+    pub length: i64,
+    /// the path inside the torrent
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FileStat {
+    #[serde(rename = "bytesCompleted")]
+    pub bytes_completed: i64,
+    pub wanted: bool,
+    pub priority: i64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Peer {
+    pub address: String,
+    #[serde(rename = "clientName")]
+    pub client_name: String,
+    #[serde(rename = "flagStr")]
+    pub flag_str: String,
+    pub progress: f32,
+    #[serde(rename = "rateToClient")]
+    pub rate_to_client: i64,
+    #[serde(rename = "rateToPeer")]
+    pub rate_to_peer: i64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackerStat {
+    pub host: String,
+    pub announce: String,
+    #[serde(rename = "lastAnnounceResult")]
+    pub last_announce_result: String,
+    #[serde(rename = "lastAnnounceSucceeded")]
+    pub last_announce_succeeded: bool,
+    #[serde(rename = "lastAnnounceTime")]
+    pub last_announce_time: i64,
+    #[serde(rename = "nextAnnounceTime")]
+    pub next_announce_time: i64,
+    #[serde(rename = "seederCount")]
+    pub seeder_count: i64,
+    #[serde(rename = "leecherCount")]
+    pub leecher_count: i64,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Nothing {}
 
@@ -103,7 +162,7 @@ pub struct FreeSpace {
     pub size_bytes: Option<u64>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct SessionStats {
     pub activeTorrentCount: u64,
     pub downloadSpeed: u64,
@@ -116,7 +175,7 @@ pub struct SessionStats {
     pub current_stats: Stats,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct Stats {
     pub uploadedBytes: u64,
     pub downloadedBytes: u64,
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::u2client::types::{Config, TorrentInfo};
+
+/// seconds
+const STATIC_TTL: i64 = 86400;
+const VOLATILE_TTL: i64 = 300;
+
+struct Entry {
+    /// unix time it was fetched
+    time: i64,
+    info: TorrentInfo,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    /// served from the static fields alone
+    pub staticHits: u64,
+    /// each one a request to details.php
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// requests to details.php the cache answered instead
+    pub fn saved(&self) -> u64 {
+        self.hits + self.staticHits
+    }
+}
+
+/// details.php by U2 torrent id; size and hash stay valid for `staticTtl`,
+/// peers, progress and promotion only for `volatileTtl`
+pub struct DetailCache {
+    staticTtl: i64,
+    volatileTtl: i64,
+    entries: Mutex<HashMap<String, Entry>>,
+    hits: AtomicU64,
+    staticHits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl DetailCache {
+    pub fn new(args: &Config) -> DetailCache {
+        DetailCache::with(
+            args.cacheStaticTtl.unwrap_or(STATIC_TTL),
+            args.cacheVolatileTtl.unwrap_or(VOLATILE_TTL),
+        )
+    }
+
+    /// a ttl of 0 caches nothing of that kind
+    pub fn with(staticTtl: i64, volatileTtl: i64) -> DetailCache {
+        DetailCache {
+            staticTtl,
+            volatileTtl: volatileTtl.min(staticTtl),
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            staticHits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn count(&self, hit: bool) {
+        if hit {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// every field, as long as the volatile ones are fresh and the promotion has not ended since
+    pub fn get(&self, uid: &str, now: i64) -> Option<TorrentInfo> {
+        let ret = match self.entries.lock() {
+            Ok(entries) => entries
+                .get(uid)
+                .filter(|x| now - x.time < self.volatileTtl)
+                .filter(|x| !matches!(x.info.promotionExpire, Some(y) if y <= now))
+                .map(|x| x.info.clone()),
+            Err(_) => None,
+        };
+        self.count(ret.is_some());
+        ret
+    }
+
+    /// (GbSize, Hash), which never change once the torrent is up;
+    /// a miss is not counted, the `get` that follows it is
+    pub fn getStatic(&self, uid: &str, now: i64) -> Option<(f32, String)> {
+        let ret = match self.entries.lock() {
+            Ok(entries) => entries
+                .get(uid)
+                .filter(|x| now - x.time < self.staticTtl)
+                .map(|x| (x.info.GbSize, x.info.Hash.to_owned())),
+            Err(_) => None,
+        };
+        if ret.is_some() {
+            self.staticHits.fetch_add(1, Ordering::Relaxed);
+        }
+        ret
+    }
+
+    /// every field while the static ones are fresh, for a caller that only relies on those;
+    /// a miss is not counted, the `get` that follows it is
+    pub fn getAged(&self, uid: &str, now: i64) -> Option<TorrentInfo> {
+        let ret = match self.entries.lock() {
+            Ok(entries) => entries
+                .get(uid)
+                .filter(|x| now - x.time < self.staticTtl)
+                .map(|x| x.info.clone()),
+            Err(_) => None,
+        };
+        if ret.is_some() {
+            self.staticHits.fetch_add(1, Ordering::Relaxed);
+        }
+        ret
+    }
+
+    /// the last known details however old, not counted as a lookup
+    pub fn peek(&self, uid: &str) -> Option<TorrentInfo> {
+        match self.entries.lock() {
+            Ok(entries) => entries.get(uid).map(|x| x.info.clone()),
+            Err(_) => None,
+        }
+    }
+
+    /// also forgets whatever expired entirely
+    pub fn put(&self, uid: &str, info: &TorrentInfo, now: i64) {
+        if self.staticTtl <= 0 {
+            return;
+        }
+        if let Ok(mut entries) = self.entries.lock() {
+            let ttl = self.staticTtl;
+            entries.retain(|_, x| now - x.time < ttl);
+            entries.insert(
+                uid.to_string(),
+                Entry {
+                    time: now,
+                    info: info.clone(),
+                },
+            );
+        }
+    }
+
+    /// forgets `uid`, whose promotion just changed
+    pub fn invalidate(&self, uid: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(uid);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|x| x.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            staticHits: self.staticHits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for DetailCache {
+    fn default() -> DetailCache {
+        DetailCache::with(STATIC_TTL, VOLATILE_TTL)
+    }
+}
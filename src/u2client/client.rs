@@ -1,32 +1,65 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Local, TimeZone};
+use log::warn;
 
 use regex::Regex;
 use reqwest::IntoUrl;
 use rss::Channel;
 use select::document::Document;
-use select::predicate::Name;
+use select::predicate::{Class, Name, Predicate};
+use sysinfo::{DiskExt, System, SystemExt};
 
+use crate::store::Store;
 use crate::torrentLib::client::{BasicAuth, TransClient};
-use crate::torrentLib::request::{Id, TorrentAction, TorrentAddArgs};
+use crate::torrentLib::request::{Id, TorrentAction, TorrentAddArgs, TorrentGetField};
 use crate::torrentLib::response::{FreeSpace, SessionGet, SessionStats, Torrent, Torrents};
+use crate::u2client::cache::{CacheStats, DetailCache};
+use crate::u2client::limiter::Limiter;
 use crate::u2client::types::UserInfo;
-use crate::u2client::types::{RssInfo, TorrentInfo};
+use crate::u2client::types::{
+    Magic, MagicRequest, MagicTarget, PeerInfo, RssInfo, SearchQuery, SeedingRecord, SitePromotion,
+    TorrentInfo, UCoinEntry,
+};
 
 use super::Result;
 
+/// one row of torrents.php
+pub(crate) struct ListRow {
+    pub title: String,
+    pub uid: String,
+    pub cat: String,
+    pub time: Option<i64>,
+    pub seeder: i32,
+    pub leecher: i32,
+}
+
 #[derive(Clone)]
 pub struct U2client {
     uid: String,
     passkey: String,
     container: reqwest::Client,
+    limiter: Arc<Limiter>,
+    cache: Arc<DetailCache>,
     torrentClient: TransClient,
     tempSpace: String,
     workSpace: String,
+    hashIndex: Arc<RwLock<HashMap<String, String>>>,
+    /// hashes neither the index nor the store know a U2 id of
+    unknownUid: Arc<RwLock<HashSet<String>>>,
 }
 
+/// the hours a magic may last on the site
+pub const MAGIC_MIN_HOURS: i32 = 24;
+pub const MAGIC_MAX_HOURS: i32 = 360;
+
+const TIME_REGEX: &str = "([0-9]{4}-[0-9]{2}-[0-9]{2} [0-9]{2}:[0-9]{2}:[0-9]{2})";
+
 impl U2client {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         cookie: &str,
         proxy: &Option<String>,
@@ -34,6 +67,8 @@ impl U2client {
         RpcUsername: &str,
         RpcPassword: &str,
         workRoot: &str,
+        limiter: Limiter,
+        cache: DetailCache,
     ) -> Result<U2client> {
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert(
@@ -55,9 +90,8 @@ impl U2client {
 
         let container = container.build()?;
 
-        let x = container
-            .get("https://u2.dmhy.org/index.php")
-            .send()
+        let x = limiter
+            .send(container.get("https://u2.dmhy.org/index.php"))
             .await?;
 
         if x.url().path() == "/index.php" {
@@ -77,28 +111,19 @@ impl U2client {
                 .ok_or("get uid failed")?
                 .to_string();
 
-            let tempSpace = format!("{}/temp", workRoot);
-            if !Path::new(&tempSpace).exists() {
-                std::fs::create_dir(&tempSpace)?;
-            }
-            let workSpace = format!("{}/work", workRoot);
-            if !Path::new(&workSpace).exists() {
-                std::fs::create_dir(&workSpace)?;
-            }
+            let (tempSpace, workSpace) = U2client::prepareWorkRoot(workRoot)?;
             let basic_auth = BasicAuth {
                 user: RpcUsername.to_string(),
                 password: RpcPassword.to_string(),
             };
-            let res = container
-                .post("https://u2.dmhy.org/getrss.php")
-                .form(&[
+            let res = limiter
+                .send(container.post("https://u2.dmhy.org/getrss.php").form(&[
                     ("inclbookmarked", 0),
                     ("inclautochecked", 1),
                     ("trackerssl", 1),
                     ("showrows", 10),
                     ("search_mode", 1),
-                ])
-                .send()
+                ]))
                 .await?
                 .text()
                 .await?;
@@ -124,23 +149,69 @@ impl U2client {
                 uid,
                 passkey,
                 container,
+                limiter: Arc::new(limiter),
+                cache: Arc::new(cache),
                 torrentClient: TransClient::with_auth(&RpcURL, basic_auth),
                 tempSpace,
                 workSpace,
+                hashIndex: Arc::new(RwLock::new(HashMap::new())),
+                unknownUid: Arc::new(RwLock::new(HashSet::new())),
             })
         } else {
             Err("illegal cookie".into())
         }
     }
-    pub async fn removeTorrent(&self, id: String) -> Result<()> {
-        let _ = self
+    /// a client which never logs into U2, only the transmission side is usable
+    pub fn withTransmission(torrentClient: TransClient, workRoot: &str) -> Result<U2client> {
+        let (tempSpace, workSpace) = U2client::prepareWorkRoot(workRoot)?;
+        Ok(U2client {
+            uid: String::new(),
+            passkey: String::new(),
+            container: reqwest::Client::builder().build()?,
+            limiter: Arc::new(Limiter::default()),
+            cache: Arc::new(DetailCache::default()),
+            torrentClient,
+            tempSpace,
+            workSpace,
+            hashIndex: Arc::new(RwLock::new(HashMap::new())),
+            unknownUid: Arc::new(RwLock::new(HashSet::new())),
+        })
+    }
+    /// how long requests to U2 stay refused since the site looked down
+    pub fn pausedFor(&self) -> Option<std::time::Duration> {
+        self.limiter.pausedFor()
+    }
+    /// the last known details of a torrent in the client, without asking U2
+    pub fn cachedInfo(&self, hash: &str, store: &Store) -> Option<TorrentInfo> {
+        self.cache.peek(&self.uidOf(hash, store).ok()??)
+    }
+    pub fn cacheStats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+    fn prepareWorkRoot(workRoot: &str) -> Result<(String, String)> {
+        let tempSpace = format!("{}/temp", workRoot);
+        if !Path::new(&tempSpace).exists() {
+            std::fs::create_dir(&tempSpace)?;
+        }
+        let workSpace = format!("{}/work", workRoot);
+        if !Path::new(&workSpace).exists() {
+            std::fs::create_dir(&workSpace)?;
+        }
+        Ok((tempSpace, workSpace))
+    }
+    /// `deleteData` also deletes the downloaded files
+    pub async fn removeTorrent(&self, id: String, deleteData: bool) -> Result<()> {
+        let res = self
             .torrentClient
-            .torrent_remove(vec![Id::Hash(id)], true)
+            .torrent_remove(vec![Id::Hash(id)], deleteData)
             .await?;
+        if !res.is_ok() {
+            return Err(format!("removeTorrent:{}", res.result).into());
+        }
         Ok(())
     }
-    pub async fn addTorrent(&self, url: &str) -> Result<()> {
-        let s = self.container.get(url).send().await?;
+    pub async fn addTorrent(&self, url: &str, paused: bool) -> Result<()> {
+        let s = self.limiter.send(self.container.get(url)).await?;
         let contentDisposition = s
             .headers()
             .get("content-disposition")
@@ -159,6 +230,7 @@ impl U2client {
         let add: TorrentAddArgs = TorrentAddArgs {
             filename: Some(to),
             download_dir: Some(self.workSpace.clone()),
+            paused: Some(paused),
             ..TorrentAddArgs::default()
         };
         let _ = self.torrentClient.torrent_add(add).await?;
@@ -169,10 +241,13 @@ impl U2client {
     }
 
     pub async fn performActionOnTorrent(&self, id: String, op: TorrentAction) -> Result<()> {
-        let _ = self
+        let res = self
             .torrentClient
             .torrent_action(op, vec![Id::Hash(id)])
             .await?;
+        if !res.is_ok() {
+            return Err(format!("performActionOnTorrent:{}", res.result).into());
+        }
         Ok(())
     }
 
@@ -180,6 +255,20 @@ impl U2client {
         Ok(self.torrentClient.torrent_get(None, None).await?.arguments)
     }
 
+    /// one torrent with its files, peers and trackers
+    pub async fn getTorrentDetail(&self, hash: &str) -> Result<Torrent> {
+        self.torrentClient
+            .torrent_get(
+                Some(TorrentGetField::detail()),
+                Some(vec![Id::Hash(hash.to_string())]),
+            )
+            .await?
+            .arguments
+            .torrents
+            .pop()
+            .ok_or_else(|| format!("getTorrentDetail:{} not found", hash).into())
+    }
+
     pub async fn getStats(&self) -> Result<SessionStats> {
         Ok(self.torrentClient.session_stats().await?.arguments)
     }
@@ -188,19 +277,36 @@ impl U2client {
         Ok(self.torrentClient.free_space(d).await?.arguments)
     }
 
-    pub async fn getDownloadList(&self) -> Result<Vec<RssInfo>> {
-        Ok(self.getTorrent().await?)
+    /// bytes free in the work directory torrents are added to,
+    /// asked from transmission or else the local disks
+    pub async fn getDiskFree(&self) -> Result<u64> {
+        let dir = &self.workSpace;
+        if let Ok(FreeSpace {
+            size_bytes: Some(x),
+            ..
+        }) = self.getFreeSpace(dir.clone()).await
+        {
+            return Ok(x);
+        }
+        let dir = dir.clone();
+        // sysinfo reads /proc and /sys, off the runtime threads
+        tokio::task::spawn_blocking(move || -> Result<u64> {
+            let mut sys = System::new();
+            sys.refresh_disks_list();
+            let free = sys
+                .get_disks()
+                .iter()
+                .filter(|x| Path::new(&dir).starts_with(x.get_mount_point()))
+                .max_by_key(|x| x.get_mount_point().as_os_str().len())
+                .map(|x| x.get_available_space());
+            Ok(free.ok_or(format!("getDiskFree:no disk holds {}", dir))?)
+        })
+        .await?
     }
 
-    pub async fn getRemove(&self) -> Result<Vec<Torrent>> {
-        let mut torrent = self.getWorkingTorrent().await?;
-        torrent.torrents.sort_by_key(|x| {
-            (
-                x.peers_getting_from_us.unwrap_or(0),
-                x.added_date.unwrap_or(0),
-            )
-        });
-        Ok(torrent.torrents.into_iter().take(5).collect())
+    /// `present` are the hashes already in the client, whose details may be stale
+    pub async fn getDownloadList(&self, present: &HashSet<String>) -> Result<Vec<RssInfo>> {
+        Ok(self.getTorrent(present).await?)
     }
 
     pub async fn getUserInfo(&self) -> Result<UserInfo> {
@@ -253,33 +359,93 @@ impl U2client {
             timeRate,
         })
     }
-    /// 2 => Free
-    /// 3 => 2x
-    /// 4 => 2xFree
-    /// 5 => 50%off
-    /// 6 => 2x50%off
-    /// 7 => 30%off
-    pub async fn applyMagic(&self, uid: &str, time: i32, magic: i32) -> Result<()> {
-        let time = time.max(24);
+    pub async fn applyMagic(&self, uid: &str, request: &MagicRequest) -> Result<()> {
         let url = format!(
             "https://u2.dmhy.org/promotion.php?action=magic&torrent={}",
             uid
         );
-        let post = [
+        let post = U2client::magicForm(uid, request)?;
+        let res = self
+            .limiter
+            .send(self.container.post(&url).form(&post))
+            .await?;
+        if res.status().as_u16() != 200 {
+            return Err("apply magic failed:network failed".into());
+        }
+        U2client::parseMagicReply(&res.text().await?)?;
+        self.cache.invalidate(uid);
+        Ok(())
+    }
+    /// promotion.php answers with a message box, its `h2` heading says whether the magic was cast
+    pub(crate) fn parseMagicReply(context: &str) -> Result<()> {
+        let doc = Document::from(context);
+        let heading = doc
+            .find(Name("h2"))
+            .map(|x| x.text().trim().to_string())
+            .find(|x| !x.is_empty());
+        if heading.as_deref().is_some_and(|x| x.contains("成功")) {
+            return Ok(());
+        }
+        let reason = doc
+            .find(Name("td").and(Class("text")))
+            .map(|x| x.text().trim().to_string())
+            .find(|x| !x.is_empty())
+            .or(heading)
+            .unwrap_or_else(|| "unknown response".to_string());
+        Err(format!("apply magic failed:{}", reason).into())
+    }
+    /// the UCoin the site would charge for `request`, nothing is cast
+    pub async fn previewMagic(&self, uid: &str, request: &MagicRequest) -> Result<f64> {
+        let post = U2client::magicForm(uid, request)?;
+        let context = self
+            .limiter
+            .send(
+                self.container
+                    .post("https://u2.dmhy.org/promotion.php?test=1")
+                    .form(&post),
+            )
+            .await?
+            .text()
+            .await?;
+        let price = U2client::matchRegex(&context, "\"price\"[' ']*:[' ']*\"?([0-9.,]+)")?;
+        Ok(price.replace(',', "").parse::<f64>()?)
+    }
+    pub(crate) fn magicForm(
+        uid: &str,
+        request: &MagicRequest,
+    ) -> Result<Vec<(&'static str, String)>> {
+        let hours = request.hours;
+        if !(MAGIC_MIN_HOURS..=MAGIC_MAX_HOURS).contains(&hours) {
+            return Err(format!(
+                "apply magic failed:{} hours out of {}..={}",
+                hours, MAGIC_MIN_HOURS, MAGIC_MAX_HOURS
+            )
+            .into());
+        }
+        let mut post = vec![
             ("action", "magic".to_string()),
             ("torrent", uid.to_string()),
-            ("user", "SELF".to_string()),
-            ("hours", time.to_string()),
-            ("promotion", magic.to_string()),
+            ("hours", hours.to_string()),
+            ("promotion", request.magic.code().to_string()),
         ];
-        let res = self.container.post(&url).form(&post).send().await?;
-        if res.status().as_u16() == 200 {
-            Ok(())
-        } else {
-            Err("apply magic failed:network failed".into())
+        match request.target {
+            MagicTarget::Own => post.push(("user", "SELF".to_string())),
+            MagicTarget::All => post.push(("user", "ALL".to_string())),
+            MagicTarget::User(ref x) => {
+                post.push(("user", "OTHER".to_string()));
+                post.push(("user_other", x.to_owned()));
+            }
         }
+        if let Magic::Custom { upload, download } = request.magic {
+            if !(1.0..=2.33).contains(&upload) || !(0.0..=0.8).contains(&download) {
+                return Err("apply magic failed:custom ratio out of range".into());
+            }
+            post.push(("ur", format!("{:.2}", upload)));
+            post.push(("dr", format!("{:.2}", download)));
+        }
+        Ok(post)
     }
-    pub async fn getTorrent(&self) -> Result<Vec<RssInfo>> {
+    pub async fn getTorrent(&self, present: &HashSet<String>) -> Result<Vec<RssInfo>> {
         let url = format!(
             "https://u2.dmhy.org/torrentrss.php?rows=50&trackerssl=1&passkey={}",
             self.passkey
@@ -291,12 +457,18 @@ impl U2client {
             let url = x.enclosure.clone().ok_or("getTorrent:bad rss feed")?.url;
             let cat = x.categories[0].name.clone();
             let uid = U2client::matchRegex(url.as_str(), "id=([0-9]+)")?;
-            let U2Info = self.getTorrentInfo(&uid).await?;
+            let time = x
+                .pub_date
+                .as_ref()
+                .and_then(|x| DateTime::parse_from_rfc2822(x).ok())
+                .map(|x| x.timestamp());
+            let U2Info = self.getFeedInfo(&uid, present).await?;
             Ok(RssInfo {
                 title,
                 url,
                 cat,
                 uid,
+                time,
                 U2Info,
             })
         });
@@ -304,11 +476,394 @@ impl U2client {
         let res: Vec<Result<RssInfo>> = futures::future::join_all(res).await;
         let mut ret = Vec::new();
         for x in res.into_iter() {
-            ret.push(x?);
+            match x {
+                Ok(x) => ret.push(x),
+                Err(e) => warn!("getTorrent:skip a torrent, {}", e),
+            }
+        }
+        Ok(ret)
+    }
+    pub async fn search_torrents(
+        &self,
+        query: &SearchQuery,
+        present: &HashSet<String>,
+    ) -> Result<Vec<RssInfo>> {
+        let mut param = vec![
+            (
+                "incldead",
+                if query.includeDead { "1" } else { "0" }.to_string(),
+            ),
+            ("page", query.page.to_string()),
+        ];
+        if let Some(ref x) = query.keyword {
+            param.push(("search", x.to_owned()));
+        }
+        if let Some(x) = query.category {
+            param.push(("cat", x.to_string()));
+        }
+        if let Some(x) = query.promotion {
+            param.push(("spstate", (x as i32).to_string()));
+        }
+        if let Some(x) = query.sort {
+            param.push(("sort", (x as i32).to_string()));
+            param.push((
+                "type",
+                if query.ascending { "asc" } else { "desc" }.to_string(),
+            ));
+        }
+        let url = reqwest::Url::parse_with_params("https://u2.dmhy.org/torrents.php", &param)?;
+        let context = self.get(url).await?;
+
+        let rows = U2client::parseTorrentList(&context)?;
+        let rows = rows.into_iter().filter(|x| {
+            query.minSeeder.iter().all(|y| x.seeder >= *y)
+                && query.maxSeeder.iter().all(|y| x.seeder <= *y)
+                && query.minLeecher.iter().all(|y| x.leecher >= *y)
+                && query.maxLeecher.iter().all(|y| x.leecher <= *y)
+        });
+        let res = rows.map(async move |x| -> Result<RssInfo> {
+            let U2Info = self.getFeedInfo(&x.uid, present).await?;
+            Ok(RssInfo {
+                title: x.title,
+                url: format!(
+                    "https://u2.dmhy.org/download.php?id={}&passkey={}&https=1",
+                    x.uid, self.passkey
+                ),
+                cat: x.cat,
+                uid: x.uid,
+                time: x.time,
+                U2Info,
+            })
+        });
+        let res: Vec<Result<RssInfo>> = futures::future::join_all(res).await;
+        let mut ret = Vec::new();
+        for x in res.into_iter() {
+            match x {
+                Ok(x) => ret.push(x),
+                Err(e) => warn!("search_torrents:skip a torrent, {}", e),
+            }
+        }
+        Ok(ret)
+    }
+    pub(crate) fn parseTorrentList(context: &str) -> Result<Vec<ListRow>> {
+        let doc = Document::from(context);
+        let table = doc
+            .find(Class("torrents"))
+            .next()
+            .ok_or("parseTorrentList:can not find torrent table")?;
+        let tbody = table.find(Name("tbody")).next().unwrap_or(table);
+        let mut ret = Vec::new();
+        for row in tbody.children().filter(|x| x.is(Name("tr"))) {
+            let col: Vec<_> = row.children().filter(|x| x.is(Name("td"))).collect();
+            if col.len() < 7 || col[0].is(Class("colhead")) {
+                continue;
+            }
+            let cat = col[0]
+                .find(Name("img"))
+                .next()
+                .and_then(|x| x.attr("alt").or_else(|| x.attr("title")))
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| col[0].text().trim().to_string());
+            let link = col[1]
+                .find(Name("a"))
+                .find(|x| match x.attr("href") {
+                    Some(x) => x.starts_with("details.php?id="),
+                    _ => false,
+                })
+                .ok_or("parseTorrentList:can not find details link")?;
+            let title = link.text().trim().to_string();
+            let uid = U2client::matchRegex(link.attr("href").unwrap_or(""), "id=([0-9]+)")?;
+            let time = match U2client::matchRegex(&col[3].html(), TIME_REGEX) {
+                Ok(x) => Some(U2client::parseTime(&x)?),
+                Err(_) => None,
+            };
+            let seeder = col[5].text().trim().replace(',', "").parse::<i32>()?;
+            let leecher = col[6].text().trim().replace(',', "").parse::<i32>()?;
+            ret.push(ListRow {
+                title,
+                uid,
+                cat,
+                time,
+                seeder,
+                leecher,
+            });
+        }
+        Ok(ret)
+    }
+    /// the site-wide promotion shown on torrents.php, if any
+    pub async fn getSitePromotion(&self) -> Result<Option<SitePromotion>> {
+        let context = self.get("https://u2.dmhy.org/torrents.php").await?;
+        U2client::parseSitePromotion(&context)
+    }
+    pub(crate) fn parseSitePromotion(context: &str) -> Result<Option<SitePromotion>> {
+        let text = Document::from(context)
+            .find(Name("body"))
+            .next()
+            .ok_or("parseSitePromotion:can not find body")?
+            .text();
+        let caps =
+            match Regex::new("(?i)全站[^0-9a-z%\n]*(2x ?free|2x ?50%|free|2x|50%|30%)([^\n]*)")?
+                .captures(&text)
+            {
+                Some(x) => x,
+                None => return Ok(None),
+            };
+        let (uploadFX, downloadFX) =
+            U2client::fxOf(&caps[1]).ok_or("parseSitePromotion:unknown promotion")?;
+        let expire = match U2client::matchRegex(&caps[2], TIME_REGEX) {
+            Ok(x) => Some(U2client::parseTime(&x)?),
+            Err(_) => None,
+        };
+        Ok(Some(SitePromotion {
+            uploadFX,
+            downloadFX,
+            expire,
+        }))
+    }
+    /// the U2 id of a torrent whose details have been fetched before
+    pub fn lookupUid(&self, hash: &str) -> Option<String> {
+        match self.hashIndex.read() {
+            Ok(x) => x.get(hash).cloned(),
+            Err(_) => None,
+        }
+    }
+    /// the U2 id of a torrent, from the index or else the torrents the tool added itself;
+    /// ids found in the store are indexed for next time, and so are hashes it has none of
+    pub fn uidOf(&self, hash: &str, store: &Store) -> Result<Option<String>> {
+        if let Some(x) = self.lookupUid(hash) {
+            return Ok(Some(x));
+        }
+        if matches!(self.unknownUid.read(), Ok(x) if x.contains(hash)) {
+            return Ok(None);
+        }
+        let uid = store.added(hash)?.map(|x| x.uid);
+        match uid {
+            Some(ref uid) => {
+                if let Ok(mut hashIndex) = self.hashIndex.write() {
+                    hashIndex.insert(hash.to_string(), uid.to_owned());
+                }
+            }
+            None => {
+                if let Ok(mut unknownUid) = self.unknownUid.write() {
+                    unknownUid.insert(hash.to_string());
+                }
+            }
+        }
+        Ok(uid)
+    }
+    pub async fn getSeedingRecords(&self) -> Result<Vec<SeedingRecord>> {
+        let context = self
+            .get(format!(
+                "https://u2.dmhy.org/getusertorrentlistajax.php?userid={}&type=completed",
+                self.uid
+            ))
+            .await?;
+        U2client::parseSeedingRecords(&context)
+    }
+    pub(crate) fn parseSeedingRecords(context: &str) -> Result<Vec<SeedingRecord>> {
+        let doc = Document::from(context);
+        let mut ret = Vec::new();
+        for table in doc.find(Name("table")) {
+            let mut rows = table.find(Name("tr"));
+            let head: Vec<String> = match rows.next() {
+                Some(x) => x
+                    .find(Name("td"))
+                    .map(|x| x.text().trim().to_string())
+                    .collect(),
+                None => continue,
+            };
+            let col = |name: &str| head.iter().position(|x| x.contains(name));
+            let (title, uploaded, downloaded, ratio, seedTime) = match (
+                col("标题"),
+                col("上传"),
+                col("下载"),
+                col("分享率"),
+                col("做种时间"),
+            ) {
+                (Some(a), Some(b), Some(c), Some(d), Some(e)) => (a, b, c, d, e),
+                _ => continue,
+            };
+            for row in rows {
+                let cell: Vec<_> = row.children().filter(|x| x.is(Name("td"))).collect();
+                if cell.len() < head.len() {
+                    continue;
+                }
+                let uid = match cell[title]
+                    .find(Name("a"))
+                    .filter_map(|x| x.attr("href"))
+                    .find(|x| x.contains("details.php?id="))
+                {
+                    Some(x) => U2client::matchRegex(x, "id=([0-9]+)")?,
+                    None => continue,
+                };
+                let amount = |x: &str| U2client::toGbSize(x).unwrap_or(0.0);
+                ret.push(SeedingRecord {
+                    uid,
+                    uploaded: amount(&cell[uploaded].text()),
+                    downloaded: amount(&cell[downloaded].text()),
+                    ratio: U2client::matchRegex(&cell[ratio].text(), "([0-9]+[.]?[0-9]*)")
+                        .map(|x| x.parse::<f32>().unwrap_or(0.0))
+                        .unwrap_or(f32::INFINITY),
+                    seedTime: U2client::toSeconds(&cell[seedTime].text())?,
+                });
+            }
         }
         Ok(ret)
     }
+    /// the info hash of a U2 torrent, from the index if we have seen it before
+    pub async fn getTorrentHash(&self, uid: &str) -> Result<String> {
+        let known = match self.hashIndex.read() {
+            Ok(x) => x.iter().find(|x| x.1 == uid).map(|x| x.0.to_owned()),
+            Err(_) => None,
+        };
+        if let Some(x) = known {
+            return Ok(x);
+        }
+        match self.cache.getStatic(uid, Local::now().timestamp()) {
+            Some((_, x)) => Ok(x),
+            None => Ok(self.getTorrentInfo(uid).await?.Hash),
+        }
+    }
+    pub async fn getUCoinLog(&self, page: u32) -> Result<Vec<UCoinEntry>> {
+        let context = self
+            .get(format!("https://u2.dmhy.org/ucoin.php?page={}", page))
+            .await?;
+        U2client::parseUCoinLog(&context)
+    }
+    pub(crate) fn parseUCoinLog(context: &str) -> Result<Vec<UCoinEntry>> {
+        let doc = Document::from(context);
+        let mut ret = Vec::new();
+        for table in doc.find(Name("table")) {
+            let mut rows = table.find(Name("tr"));
+            let head: Vec<String> = match rows.next() {
+                Some(x) => x
+                    .find(Name("td"))
+                    .map(|x| x.text().trim().to_string())
+                    .collect(),
+                None => continue,
+            };
+            let col = |names: &[&str]| {
+                head.iter()
+                    .position(|x| names.iter().any(|name| x.contains(name)))
+            };
+            let (time, amount, reason) = match (
+                col(&["时间"]),
+                col(&["数额", "变动", "UCoin"]),
+                col(&["原因", "说明", "备注"]),
+            ) {
+                (Some(a), Some(b), Some(c)) => (a, b, c),
+                _ => continue,
+            };
+            for row in rows {
+                let cell: Vec<_> = row.find(Name("td")).collect();
+                if cell.len() < head.len() {
+                    continue;
+                }
+                let torrent = cell[reason]
+                    .find(Name("a"))
+                    .filter_map(|x| x.attr("href"))
+                    .find(|x| x.contains("details.php?id="))
+                    .and_then(|x| U2client::matchRegex(x, "id=([0-9]+)").ok());
+                ret.push(UCoinEntry {
+                    time: U2client::parseTime(&U2client::matchRegex(
+                        &cell[time].html(),
+                        TIME_REGEX,
+                    )?)?,
+                    amount: U2client::matchRegex(&cell[amount].text(), "([-+]?[0-9.,]+)")?
+                        .replace(',', "")
+                        .parse::<f64>()?,
+                    reason: cell[reason].text().trim().to_string(),
+                    torrent,
+                });
+            }
+        }
+        Ok(ret)
+    }
+    /// the UCoin we earn per hour, as shown on the bonus page
+    pub async fn getBonusRate(&self) -> Result<f64> {
+        let context = self.get("https://u2.dmhy.org/mpbonus.php").await?;
+        let text = Document::from(context.as_str())
+            .find(Name("body"))
+            .next()
+            .ok_or("getBonusRate:can not find body")?
+            .text();
+        Ok(U2client::matchRegex(&text, "每小时[^0-9]*([0-9.,]+)")?
+            .replace(',', "")
+            .parse::<f64>()?)
+    }
+    pub async fn getTorrentPeers(&self, idx: &str) -> Result<Vec<PeerInfo>> {
+        let context = self
+            .get(format!("https://u2.dmhy.org/viewpeerlist.php?id={}", idx))
+            .await?;
+        U2client::parsePeerList(&context)
+    }
+    pub(crate) fn parsePeerList(context: &str) -> Result<Vec<PeerInfo>> {
+        let doc = Document::from(context);
+        let mut ret = Vec::new();
+        for table in doc.find(Name("table")) {
+            let mut rows = table.find(Name("tr"));
+            let head: Vec<String> = match rows.next() {
+                Some(x) => x
+                    .find(Name("td"))
+                    .map(|x| x.text().trim().to_string())
+                    .collect(),
+                None => continue,
+            };
+            let col = |name: &str| head.iter().position(|x| x == name);
+            let (connectable, uploaded, downloaded, progress, client) = match (
+                col("可连接"),
+                col("上传"),
+                col("下载"),
+                col("完成"),
+                col("客户端"),
+            ) {
+                (Some(a), Some(b), Some(c), Some(d), Some(e)) => (a, b, c, d, e),
+                _ => continue,
+            };
+            for row in rows {
+                let cell: Vec<String> = row.find(Name("td")).map(|x| x.text()).collect();
+                if cell.len() < head.len() {
+                    continue;
+                }
+                let amount = |x: &str| U2client::toGbSize(x).unwrap_or(0.0);
+                ret.push(PeerInfo {
+                    connectable: cell[connectable].trim() == "是",
+                    uploaded: amount(&cell[uploaded]),
+                    downloaded: amount(&cell[downloaded]),
+                    progress: U2client::matchRegex(&cell[progress], "([0-9.]+)%")?
+                        .parse::<f32>()?
+                        / 100.0,
+                    client: cell[client].trim().to_string(),
+                });
+            }
+        }
+        Ok(ret)
+    }
+    /// served from the cache while fresh
     pub async fn getTorrentInfo(&self, idx: &str) -> Result<TorrentInfo> {
+        let now = Local::now().timestamp();
+        if let Some(x) = self.cache.get(idx, now) {
+            return Ok(x);
+        }
+        let info = self.fetchTorrentInfo(idx).await?;
+        self.cache.put(idx, &info, now);
+        Ok(info)
+    }
+    /// the details of a feed item, possibly stale if its hash is in `present`,
+    /// as promote only checks those against the hash
+    async fn getFeedInfo(&self, idx: &str, present: &HashSet<String>) -> Result<TorrentInfo> {
+        let now = Local::now().timestamp();
+        let aged = self
+            .cache
+            .peek(idx)
+            .filter(|x| present.contains(&x.Hash))
+            .and_then(|_| self.cache.getAged(idx, now));
+        match aged {
+            Some(x) => Ok(x),
+            None => self.getTorrentInfo(idx).await,
+        }
+    }
+    async fn fetchTorrentInfo(&self, idx: &str) -> Result<TorrentInfo> {
         let toNumber = |x: &str| -> Result<f32> {
             Ok(U2client::matchRegex(&x.to_string(), "([0-9.]+)")?.parse::<f32>()?)
         };
@@ -333,12 +888,6 @@ impl U2client {
                 .attr("alt")
                 .ok_or("getTorrentInfo:can find alt for fx")?;
             match typeNode {
-                "FREE" => (1.0, 0.0),
-                "2X Free" => (2.0, 0.0),
-                "30%" => (1.0, 0.3),
-                "2X 50%" => (2.0, 0.5),
-                "50%" => (1.0, 0.5),
-                "2X" => (2.0, 1.0),
                 "Promotion" => {
                     let mut iters = sink.find(Name("b"));
 
@@ -356,25 +905,19 @@ impl U2client {
                     )?;
                     (f, s)
                 }
-                _ => (1.0, 1.0),
+                x => U2client::fxOf(x).unwrap_or((1.0, 1.0)),
             }
         } else {
             (1.0, 1.0)
         };
+        let promotionExpire = match U2client::matchRegex(&sink.html(), TIME_REGEX) {
+            Ok(x) => Some(U2client::parseTime(&x)?),
+            Err(_) => None,
+        };
 
         let s = U2client::reduceToText(&body, "基本信息")?;
         let size = U2client::matchRegex(&s, "大小:[' ']*([0-9.' ']+[TGMK]iB)")?;
-        let number = toNumber(&*size)?;
-        let GbSize = match size
-            .chars()
-            .nth(size.len() - 3)
-            .ok_or("getTorrentInfo:bad torrent size")?
-        {
-            'T' => number * 1024.0,
-            'G' => number,
-            'M' => number / 1024.0,
-            _ => number / 1024.0 / 1024.0,
-        };
+        let GbSize = U2client::toGbSize(&size)?;
 
         let s = U2client::reduceToText(&body, "同伴[查看列表][隐藏列表]")?;
         let seeder = U2client::matchRegex(&s, "([0-9]+)[' ']*个做种者")?.parse::<i32>()?;
@@ -387,10 +930,14 @@ impl U2client {
 
         let s = U2client::reduceToText(&body, "种子信息")?;
         let Hash = U2client::matchRegex(&s, "种子散列值:[' ']*([0-9a-z]*)[' ']*")?;
+        if let Ok(mut hashIndex) = self.hashIndex.write() {
+            hashIndex.insert(Hash.to_owned(), idx.to_string());
+        }
         Ok(TorrentInfo {
             GbSize,
             uploadFX,
             downloadFX,
+            promotionExpire,
             seeder,
             leecher,
             avgProgress,
@@ -401,7 +948,7 @@ impl U2client {
     where
         T: IntoUrl,
     {
-        let ret = self.container.get(url).send().await?;
+        let ret = self.limiter.send(self.container.get(url)).await?;
         if ret.status().as_u16() == 200 {
             Ok(ret.text().await?)
         } else {
@@ -409,6 +956,53 @@ impl U2client {
         }
     }
 
+    /// (uploadFX, downloadFX) of a promotion name like "2X Free"
+    fn fxOf(name: &str) -> Option<(f32, f32)> {
+        match name.to_lowercase().replace(' ', "").as_str() {
+            "free" => Some((1.0, 0.0)),
+            "2xfree" => Some((2.0, 0.0)),
+            "30%" => Some((1.0, 0.3)),
+            "2x50%" => Some((2.0, 0.5)),
+            "50%" => Some((1.0, 0.5)),
+            "2x" => Some((2.0, 1.0)),
+            _ => None,
+        }
+    }
+
+    /// the site shows time in our local time zone
+    fn parseTime(time: &str) -> Result<i64> {
+        Ok(Local
+            .datetime_from_str(time, "%Y-%m-%d %H:%M:%S")?
+            .timestamp())
+    }
+
+    /// "1天 02:03:04" or "02:03:04"
+    fn toSeconds(time: &str) -> Result<i64> {
+        let days = U2client::matchRegex(time, "([0-9]+)[' ']*天")
+            .map(|x| x.parse::<i64>().unwrap_or(0))
+            .unwrap_or(0);
+        let caps = Regex::new("([0-9]+):([0-9]+):([0-9]+)")?.captures(time);
+        let rest = match caps {
+            Some(x) => {
+                x[1].parse::<i64>()? * 3600 + x[2].parse::<i64>()? * 60 + x[3].parse::<i64>()?
+            }
+            None => 0,
+        };
+        Ok(days * 86400 + rest)
+    }
+
+    /// "1.5 TiB" and the like in GiB
+    pub fn toGbSize(size: &str) -> Result<f32> {
+        let number = U2client::matchRegex(size, "([0-9.]+)")?.parse::<f32>()?;
+        let unit = U2client::matchRegex(size, "([TGMK])iB")?;
+        Ok(match unit.as_str() {
+            "T" => number * 1024.0,
+            "G" => number,
+            "M" => number / 1024.0,
+            _ => number / 1024.0 / 1024.0,
+        })
+    }
+
     fn matchRegex(src: &str, reg: &str) -> Result<String> {
         Ok(Regex::new(reg)?
             .captures_iter(src)
@@ -0,0 +1,244 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+
+use log::{error, info, warn};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+
+use crate::u2client::types::Config;
+use crate::Result;
+
+/// the first retry waits about this long, doubling after every failure
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(120);
+
+const REQUESTS_PER_SECOND: f64 = 2.0;
+const CONCURRENCY: usize = 4;
+const RETRIES: u32 = 3;
+const THRESHOLD: u32 = 5;
+/// seconds
+const COOLDOWN: u64 = 300;
+
+struct State {
+    /// the earliest a request may start
+    nextSlot: Instant,
+    /// requests failed in a row, retries included in a single one
+    failures: u32,
+    openUntil: Option<Instant>,
+}
+
+/// paces every request to U2, retries what the site refused or dropped,
+/// and stops talking to the site for a while once it looks down
+pub struct Limiter {
+    interval: Duration,
+    permits: Semaphore,
+    retries: u32,
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+/// a random duration in `[x / 2, x]`
+fn jitter(x: Duration) -> Duration {
+    let half = x.as_millis() as u64 / 2;
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(half + random % (half + 1))
+}
+
+/// whether the site asked us to slow down or could not answer
+fn isOverloaded(x: StatusCode) -> bool {
+    x == StatusCode::TOO_MANY_REQUESTS || x.is_server_error()
+}
+
+/// whether the site refused the cookie, redirects are followed to the login page
+fn isAuthFailure(x: &Response) -> bool {
+    x.status() == StatusCode::UNAUTHORIZED
+        || x.status() == StatusCode::FORBIDDEN
+        || x.url().path().ends_with("login.php")
+}
+
+/// seconds in a `Retry-After` header, the http date form is ignored
+fn retryAfter(x: &Response) -> Option<Duration> {
+    x.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+impl Limiter {
+    pub fn new(args: &Config) -> Limiter {
+        Limiter::with(
+            args.requestsPerSecond.unwrap_or(REQUESTS_PER_SECOND),
+            args.maxConcurrentRequests.unwrap_or(CONCURRENCY),
+            args.maxRetries.unwrap_or(RETRIES),
+            args.circuitThreshold.unwrap_or(THRESHOLD),
+            Duration::from_secs(args.circuitCooldown.unwrap_or(COOLDOWN)),
+        )
+    }
+
+    /// `requestsPerSecond` of 0 or less does not pace at all
+    pub fn with(
+        requestsPerSecond: f64,
+        concurrency: usize,
+        retries: u32,
+        threshold: u32,
+        cooldown: Duration,
+    ) -> Limiter {
+        let interval = if requestsPerSecond > 0.0 {
+            Duration::from_secs_f64(1.0 / requestsPerSecond)
+        } else {
+            Duration::from_secs(0)
+        };
+        Limiter {
+            interval,
+            permits: Semaphore::new(concurrency.max(1)),
+            retries,
+            threshold: threshold.max(1),
+            cooldown,
+            state: Mutex::new(State {
+                nextSlot: Instant::now(),
+                failures: 0,
+                openUntil: None,
+            }),
+        }
+    }
+
+    fn state(&self) -> Result<std::sync::MutexGuard<'_, State>> {
+        Ok(self.state.lock().map_err(|_| "limiter:lock failed")?)
+    }
+
+    /// how long requests stay refused, `None` while the site is deemed up
+    pub fn pausedFor(&self) -> Option<Duration> {
+        let state = self.state.lock().ok()?;
+        let until = state.openUntil?;
+        let now = Instant::now();
+        if until > now {
+            Some(until - now)
+        } else {
+            None
+        }
+    }
+
+    /// the delay before retry number `attempt`, counted from 0
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let x = BACKOFF_BASE
+            .checked_mul(1 << attempt.min(16))
+            .unwrap_or(BACKOFF_MAX)
+            .min(BACKOFF_MAX);
+        jitter(x)
+    }
+
+    /// waits for a free connection and the next slot, fails at once while the circuit is open
+    async fn acquire(&self) -> Result<tokio::sync::SemaphorePermit<'_>> {
+        if let Some(x) = self.pausedFor() {
+            return Err(format!("limiter:site down, paused for {}s", x.as_secs()).into());
+        }
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|_| "limiter:semaphore closed")?;
+        let slot = {
+            let mut state = self.state()?;
+            let slot = state.nextSlot.max(Instant::now());
+            state.nextSlot = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
+        Ok(permit)
+    }
+
+    fn succeed(&self) -> Result<()> {
+        let mut state = self.state()?;
+        if state.openUntil.take().is_some() {
+            info!("limiter:site is back");
+        }
+        state.failures = 0;
+        Ok(())
+    }
+
+    fn fail(&self) -> Result<()> {
+        let mut state = self.state()?;
+        state.failures += 1;
+        // after the cooldown a single failure is enough to open it again
+        if state.failures >= self.threshold {
+            error!(
+                "limiter:{} requests failed in a row, pausing for {}s",
+                state.failures,
+                self.cooldown.as_secs()
+            );
+            state.openUntil = Some(Instant::now() + self.cooldown);
+        }
+        Ok(())
+    }
+
+    /// sends `request`, retrying network errors, 429 and 5xx with exponential backoff;
+    /// a refused login fails at once and counts against the circuit, any other response
+    /// is returned as is
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let this = request
+                .try_clone()
+                .ok_or("limiter:a streamed body can not be retried")?;
+            let last = attempt >= self.retries;
+            let res = {
+                let _permit = self.acquire().await?;
+                this.send().await
+            };
+            let wait = match res {
+                // retrying will not log us in again
+                Ok(x) if isAuthFailure(&x) => {
+                    error!(
+                        "limiter:{} answered {}, check the cookie",
+                        x.url(),
+                        x.status()
+                    );
+                    self.fail()?;
+                    return Err(format!("limiter:{} refused the login", x.url()).into());
+                }
+                Ok(x) if !isOverloaded(x.status()) => {
+                    self.succeed()?;
+                    return Ok(x);
+                }
+                Ok(x) => {
+                    warn!("limiter:{} answered {}", x.url(), x.status());
+                    if last {
+                        self.fail()?;
+                        return Err(format!("limiter:{} answered {}", x.url(), x.status()).into());
+                    }
+                    let backoff = self.backoff(attempt);
+                    retryAfter(&x).map_or(backoff, |y| y.max(backoff))
+                }
+                Err(x) => {
+                    warn!("limiter:{}", x);
+                    if last {
+                        self.fail()?;
+                        return Err(x.into());
+                    }
+                    self.backoff(attempt)
+                }
+            };
+            sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl Default for Limiter {
+    fn default() -> Limiter {
+        Limiter::with(
+            REQUESTS_PER_SECOND,
+            CONCURRENCY,
+            RETRIES,
+            THRESHOLD,
+            Duration::from_secs(COOLDOWN),
+        )
+    }
+}
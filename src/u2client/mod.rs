@@ -1,4 +1,6 @@
 pub use crate::Result;
 
+pub mod cache;
 pub mod client;
+pub mod limiter;
 pub mod types;
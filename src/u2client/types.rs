@@ -1,7 +1,6 @@
 use serde::Deserialize;
-use sysinfo::System;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserInfo {
     pub username: String,
 
@@ -19,26 +18,174 @@ pub struct UserInfo {
     pub timeRate: String,
 }
 
-#[derive(Debug, Clone)]
+impl UserInfo {
+    pub fn coinValue(&self) -> crate::Result<f64> {
+        Ok(self.coin.replace(',', "").trim().parse::<f64>()?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TorrentInfo {
     pub GbSize: f32,
     pub uploadFX: f32,
     pub downloadFX: f32,
+    /// unix time when the promotion ends, `None` if it never does
+    pub promotionExpire: Option<i64>,
     pub seeder: i32,
     pub leecher: i32,
     pub avgProgress: f32,
     pub Hash: String,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct UCoinEntry {
+    pub time: i64,
+    pub amount: f64,
+    pub reason: String,
+    pub torrent: Option<String>,
+}
+
+impl UCoinEntry {
+    pub fn isMagic(&self) -> bool {
+        self.reason.contains("魔法")
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UCoinSummary {
+    pub income: f64,
+    pub magic: f64,
+    pub net: f64,
+    pub hourly: Option<f64>,
+}
+
 #[derive(Debug, Clone)]
+pub struct SitePromotion {
+    pub uploadFX: f32,
+    pub downloadFX: f32,
+    pub expire: Option<i64>,
+}
+
+/// one of our completed torrents as recorded by the site
+#[derive(Debug, Clone)]
+pub struct SeedingRecord {
+    pub uid: String,
+    /// GiB
+    pub uploaded: f32,
+    /// GiB
+    pub downloaded: f32,
+    pub ratio: f32,
+    /// seconds
+    pub seedTime: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerInfo {
+    pub connectable: bool,
+    /// GiB
+    pub uploaded: f32,
+    /// GiB
+    pub downloaded: f32,
+    pub progress: f32,
+    pub client: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct RssInfo {
     pub title: String,
     pub url: String,
     pub cat: String,
     pub uid: String,
+    /// unix time of publishing
+    pub time: Option<i64>,
     pub U2Info: TorrentInfo,
 }
 
+/// the `spstate` filter of torrents.php
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Promotion {
+    Normal = 1,
+    Free = 2,
+    TwoX = 3,
+    TwoXFree = 4,
+    HalfDown = 5,
+    TwoXHalfDown = 6,
+    ThirtyDown = 7,
+}
+
+/// the `sort` column of torrents.php
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Title = 1,
+    Comments = 3,
+    Time = 4,
+    Size = 5,
+    Snatched = 6,
+    Seeder = 7,
+    Leecher = 8,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub enum Magic {
+    Free,
+    TwoX,
+    TwoXFree,
+    HalfDown,
+    TwoXHalfDown,
+    ThirtyDown,
+    Custom { upload: f32, download: f32 },
+}
+
+impl Magic {
+    /// the `promotion` field of promotion.php
+    pub fn code(&self) -> i32 {
+        match self {
+            Magic::Free => 2,
+            Magic::TwoX => 3,
+            Magic::TwoXFree => 4,
+            Magic::HalfDown => 5,
+            Magic::TwoXHalfDown => 6,
+            Magic::ThirtyDown => 7,
+            Magic::Custom { .. } => 8,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub enum MagicTarget {
+    Own,
+    All,
+    User(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct MagicRequest {
+    pub magic: Magic,
+    pub target: MagicTarget,
+    pub hours: i32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub keyword: Option<String>,
+    pub category: Option<i32>,
+    pub promotion: Option<Promotion>,
+    /// dead torrents are left out unless set
+    pub includeDead: bool,
+
+    /// the seeder and leecher bounds are checked on the rows of the fetched page,
+    /// so a page may come back with fewer rows than the site shows
+    pub minSeeder: Option<i32>,
+    pub maxSeeder: Option<i32>,
+    pub minLeecher: Option<i32>,
+    pub maxLeecher: Option<i32>,
+
+    pub sort: Option<SortBy>,
+    pub ascending: bool,
+    /// zero based
+    pub page: u32,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub cookie: String,
@@ -47,6 +194,70 @@ pub struct Config {
 
     pub downloadFxFilter: Option<f32>,
     pub GBSizeFilter: Option<f32>,
+    pub searchPages: Option<u32>,
+    pub minConnectableLeecher: Option<usize>,
+    /// minutes
+    pub expireMargin: Option<i64>,
+    pub expireAction: Option<crate::tasks::expire::ExpireAction>,
+
+    /// hours, the site's seeding requirement
+    pub minSeedTime: Option<f32>,
+    pub minSeedRatio: Option<f32>,
+
+    pub ruleMode: Option<crate::tasks::rules::RuleMode>,
+    pub rules: Option<Vec<crate::tasks::rules::Rule>>,
+
+    pub scoreWeights: Option<crate::tasks::score::ScoreWeights>,
+    pub maxAddPerCycle: Option<usize>,
+    /// GiB
+    pub maxAddSize: Option<f32>,
+    pub maxPendingSize: Option<f32>,
+
+    /// UCoin
+    pub magicBudget: Option<f64>,
+    pub magicBudgetPeriod: Option<crate::tasks::budget::BudgetPeriod>,
+
+    /// applied in order, each breaking the ties of the one before
+    pub removeStrategy: Option<Vec<String>>,
+    /// days
+    pub removeWindow: Option<f32>,
+    /// "count" or "enough"
+    pub removeMode: Option<String>,
+    pub removeCount: Option<usize>,
+
+    /// "added", "u2" or "all", the torrents maintain and expire may touch
+    pub manage: Option<String>,
+    /// name or hash regexes always managed
+    pub allowList: Option<Vec<String>>,
+    /// name or hash regexes never managed
+    pub denyList: Option<Vec<String>>,
+
+    /// GiB free in the download directory below which maintain removes torrents and promote adds none
+    pub lowWatermark: Option<f32>,
+    /// GiB free maintain removes up to once below the low watermark
+    pub highWatermark: Option<f32>,
+
+    /// `[jobs.<name>]` overrides of the schedule of promote, backEnd, maintain, expire, ucoin and snapshot
+    pub jobs: Option<std::collections::HashMap<String, crate::scheduler::JobConfig>>,
+
+    /// requests to U2 per second, 0 does not pace them
+    pub requestsPerSecond: Option<f64>,
+    /// requests to U2 in flight at once
+    pub maxConcurrentRequests: Option<usize>,
+    /// retries of a request failing with a network error, 429 or 5xx
+    pub maxRetries: Option<u32>,
+    /// failed requests in a row after which automation pauses
+    pub circuitThreshold: Option<u32>,
+    /// seconds automation pauses once the site looks down
+    pub circuitCooldown: Option<u64>,
+
+    /// seconds the size and hash from details.php are reused
+    pub cacheStaticTtl: Option<i64>,
+    /// seconds peers, progress and promotion from details.php are reused
+    pub cacheVolatileTtl: Option<i64>,
+
+    /// decide and log as usual but never add, cast magic or remove
+    pub dryRun: Option<bool>,
 
     pub RpcURL: String,
     pub RpcUsername: String,
@@ -57,10 +268,22 @@ pub struct Config {
     pub maxSize: f32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Status {
-    pub hardware: Option<System>,
+    pub hardware: Option<crate::ui::sampler::HardwareSample>,
     pub local: Option<crate::torrentLib::response::SessionStats>,
+    pub history: Option<crate::ui::history::HistoryGraph>,
+    pub torrents: Option<crate::ui::torrents::TorrentTable>,
+    pub detail: Option<crate::ui::detail::TorrentDetail>,
+    pub feed: Option<crate::ui::feed::FeedTable>,
     pub remote: Option<UserInfo>,
-    pub logDir: Option<String>,
+    pub ucoin: Option<UCoinSummary>,
+    pub log: Option<crate::ui::log::LogTable>,
+    pub jobs: Option<Vec<crate::scheduler::JobStatus>>,
+    pub cache: Option<crate::u2client::cache::CacheStats>,
+    /// the outcome of the last command from the torrent list, while fresh
+    pub toast: Option<crate::ui::actions::Toast>,
+    pub dryRun: bool,
+    /// seconds requests to U2 stay paused
+    pub sitePaused: Option<u64>,
 }
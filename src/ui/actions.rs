@@ -0,0 +1,163 @@
+use std::time::{Duration, Instant};
+
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::torrentLib::request::TorrentAction;
+use crate::ui::torrents::TorrentRow;
+
+/// how long a toast stays up
+pub const TOAST_TIME: Duration = Duration::from_secs(5);
+
+/// the keys of the list that send a command, for the title
+pub const KEYS: &str = "t start p stop v verify a announce +/-/T/B queue x remove X delete";
+
+/// what a key of the torrent list asks transmission to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Action(TorrentAction),
+    /// true also deletes the downloaded files
+    Remove(bool),
+}
+
+impl Command {
+    pub fn fromKey(key: char) -> Option<Command> {
+        Some(match key {
+            't' => Command::Action(TorrentAction::Start),
+            'p' => Command::Action(TorrentAction::Stop),
+            'v' => Command::Action(TorrentAction::Verify),
+            'a' => Command::Action(TorrentAction::Reannounce),
+            '+' => Command::Action(TorrentAction::QueueUp),
+            '-' => Command::Action(TorrentAction::QueueDown),
+            'T' => Command::Action(TorrentAction::QueueTop),
+            'B' => Command::Action(TorrentAction::QueueBottom),
+            'x' => Command::Remove(false),
+            'X' => Command::Remove(true),
+            _ => return None,
+        })
+    }
+
+    /// whether it waits for a confirmation
+    pub fn isDestructive(&self) -> bool {
+        matches!(self, Command::Remove(_))
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Command::Action(TorrentAction::Start) => "start",
+            Command::Action(TorrentAction::Stop) => "stop",
+            Command::Action(TorrentAction::StartNow) => "start now",
+            Command::Action(TorrentAction::Verify) => "verify",
+            Command::Action(TorrentAction::Reannounce) => "reannounce",
+            Command::Action(TorrentAction::QueueTop) => "queue to the top",
+            Command::Action(TorrentAction::QueueUp) => "queue up",
+            Command::Action(TorrentAction::QueueDown) => "queue down",
+            Command::Action(TorrentAction::QueueBottom) => "queue to the bottom",
+            Command::Remove(false) => "remove",
+            Command::Remove(true) => "remove with data",
+        }
+    }
+}
+
+/// a command on one torrent
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorrentCommand {
+    pub hash: String,
+    pub name: String,
+    /// bytes
+    pub size: i64,
+    pub command: Command,
+}
+
+impl TorrentCommand {
+    pub fn new(x: &TorrentRow, command: Command) -> TorrentCommand {
+        TorrentCommand {
+            hash: x.hash.to_owned(),
+            name: x.name.to_owned(),
+            size: x.size,
+            command,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self.command {
+            Command::Remove(false) => format!("remove {}, keeping its data", self.name),
+            Command::Remove(true) => format!("remove {} and delete its data", self.name),
+            x => format!("{} {}", x.describe(), self.name),
+        }
+    }
+}
+
+/// the outcome of a command, shown for `TOAST_TIME`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub text: String,
+    pub ok: bool,
+    pub time: Instant,
+}
+
+impl Toast {
+    pub fn new(text: String, ok: bool) -> Toast {
+        Toast {
+            text,
+            ok,
+            time: Instant::now(),
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        self.time.elapsed() >= TOAST_TIME
+    }
+}
+
+/// a box of `width` percent and `height` lines in the middle of `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = area.width * width / 100;
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// asks whether to `what`
+pub fn drawConfirm<B: Backend>(f: &mut Frame<B>, area: Rect, what: &str) {
+    let area = centered(area, 60, 5);
+    let text = vec![
+        Spans::from(Span::raw(format!("{}?", what))),
+        Spans::from(Span::styled(
+            "y/Enter to confirm, n/Esc to cancel",
+            Style::default().fg(Color::Yellow),
+        )),
+    ];
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .title("Confirm")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red)),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// at the bottom of `area`
+pub fn drawToast<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Toast) {
+    let height = 3.min(area.height);
+    let area = Rect::new(area.x, area.y + area.height - height, area.width, height);
+    let color = if x.ok { Color::Green } else { Color::Red };
+    let paragraph = Paragraph::new(Span::styled(x.text.to_owned(), Style::default().fg(color)))
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+use chrono::{Local, TimeZone};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Span,
+    widgets::{Block, Borders, Cell, Row, Table},
+    Frame,
+};
+
+use crate::torrentLib::response::{File, FileStat, Torrent};
+use crate::u2client::types::TorrentInfo;
+
+/// one torrent as the detail pane shows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorrentDetail {
+    /// fetched with its files, peers and trackers
+    pub torrent: Torrent,
+    /// the U2 torrent id, if known
+    pub uid: Option<String>,
+    pub info: Option<TorrentInfo>,
+}
+
+/// a line of the file tree, directories sum up the files below them
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileNode {
+    pub depth: usize,
+    pub name: String,
+    /// bytes
+    pub length: i64,
+    pub done: i64,
+    /// false if no file below is wanted
+    pub wanted: bool,
+    pub isDir: bool,
+}
+
+/// the files of a torrent as a tree in path order, `stats` being in the order of `files`
+pub fn fileTree(files: &[File], stats: &[FileStat]) -> Vec<FileNode> {
+    let sorted: BTreeMap<&str, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.name.as_str(), i))
+        .collect();
+    let mut ret: Vec<FileNode> = Vec::new();
+    // the directories the last file was in, with their index in `ret`
+    let mut open: Vec<(&str, usize)> = Vec::new();
+    for (path, i) in sorted.into_iter() {
+        let parts: Vec<&str> = path.split('/').collect();
+        let (dirs, name) = parts.split_at(parts.len() - 1);
+        let common = open
+            .iter()
+            .zip(dirs.iter())
+            .take_while(|(x, y)| x.0 == **y)
+            .count();
+        open.truncate(common);
+        for dir in dirs[common..].iter() {
+            open.push((dir, ret.len()));
+            ret.push(FileNode {
+                depth: open.len() - 1,
+                name: dir.to_string(),
+                length: 0,
+                done: 0,
+                wanted: false,
+                isDir: true,
+            });
+        }
+        let file = &files[i];
+        let wanted = !matches!(stats.get(i), Some(x) if !x.wanted);
+        for (_, idx) in open.iter() {
+            let dir = &mut ret[*idx];
+            dir.length += file.length;
+            dir.done += file.bytes_completed;
+            dir.wanted |= wanted;
+        }
+        ret.push(FileNode {
+            depth: open.len(),
+            name: name[0].to_string(),
+            length: file.length,
+            done: file.bytes_completed,
+            wanted,
+            isDir: false,
+        });
+    }
+    ret
+}
+
+fn time(x: i64) -> String {
+    if x <= 0 {
+        "-".to_string()
+    } else {
+        Local.timestamp(x, 0).format("%m-%d %H:%M:%S").to_string()
+    }
+}
+
+fn percent(done: i64, length: i64) -> String {
+    if length <= 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", done as f32 / length as f32 * 100.0)
+    }
+}
+
+fn table<'a>(title: &'a str, items: Vec<Vec<Cell<'a>>>, widths: &'a [Constraint]) -> Table<'a> {
+    let items: Vec<Row> = items.into_iter().map(Row::new).collect();
+    Table::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .widths(widths)
+}
+
+const INFO_WIDTHS: [Constraint; 4] = [
+    Constraint::Percentage(25),
+    Constraint::Percentage(25),
+    Constraint::Percentage(25),
+    Constraint::Percentage(25),
+];
+const FILE_WIDTHS: [Constraint; 3] = [
+    Constraint::Percentage(70),
+    Constraint::Percentage(15),
+    Constraint::Percentage(15),
+];
+const PEER_WIDTHS: [Constraint; 5] = [
+    Constraint::Percentage(30),
+    Constraint::Percentage(25),
+    Constraint::Percentage(15),
+    Constraint::Percentage(15),
+    Constraint::Percentage(15),
+];
+const TRACKER_WIDTHS: [Constraint; 4] = [
+    Constraint::Percentage(25),
+    Constraint::Percentage(35),
+    Constraint::Percentage(20),
+    Constraint::Percentage(20),
+];
+
+pub fn drawDetail<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<TorrentDetail>) {
+    let x = match x {
+        Some(x) => x,
+        None => {
+            let items = vec![vec![Cell::from(Span::raw("loading"))]];
+            f.render_widget(table("Detail", items, &[Constraint::Percentage(100)]), area);
+            return;
+        }
+    };
+    let chunks = Layout::default()
+        .constraints(
+            [
+                Constraint::Length(6),
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .direction(Direction::Vertical)
+        .split(area);
+    let t = &x.torrent;
+
+    let mut items = vec![vec![
+        Cell::from(Span::raw(t.hash_string.to_owned().unwrap_or_default())),
+        Cell::from(Span::raw(format!(
+            "Size {:.3}GB",
+            t.total_size.unwrap_or(0) as f32 / 1e9
+        ))),
+        Cell::from(Span::raw(format!(
+            "Done {:.1}%",
+            t.percent_done.unwrap_or(0.0) * 100.0
+        ))),
+        Cell::from(Span::raw(format!(
+            "Ratio {:.2}",
+            t.upload_ratio.unwrap_or(0.0)
+        ))),
+    ]];
+    match (&x.uid, &x.info) {
+        (Some(uid), Some(info)) => {
+            items.push(vec![
+                Cell::from(Span::raw(format!("U2 #{}", uid))),
+                Cell::from(Span::styled(
+                    format!("FX {}x/{:.0}%", info.uploadFX, info.downloadFX * 100.0),
+                    Style::default().fg(Color::Yellow),
+                )),
+                Cell::from(Span::raw(match info.promotionExpire {
+                    Some(x) => format!("Until {}", time(x)),
+                    None => "Until forever".to_string(),
+                })),
+                Cell::from(Span::raw(format!("Size {:.3}GB", info.GbSize))),
+            ]);
+            items.push(vec![
+                Cell::from(Span::raw(format!("Seeders {}", info.seeder))),
+                Cell::from(Span::raw(format!("Leechers {}", info.leecher))),
+                Cell::from(Span::raw(format!(
+                    "Avg progress {:.0}%",
+                    info.avgProgress * 100.0
+                ))),
+            ]);
+        }
+        (Some(uid), None) => items.push(vec![Cell::from(Span::raw(format!(
+            "U2 #{}, details unavailable",
+            uid
+        )))]),
+        _ => items.push(vec![Cell::from(Span::raw("not known on U2"))]),
+    }
+    if let Some(ref error) = t.error_string {
+        if !error.is_empty() {
+            items.push(vec![Cell::from(Span::styled(
+                error.to_owned(),
+                Style::default().fg(Color::Red),
+            ))]);
+        }
+    }
+    let name = t.name.to_owned().unwrap_or_default();
+    f.render_widget(table(&name, items, &INFO_WIDTHS), chunks[0]);
+
+    let files = fileTree(
+        t.files.as_deref().unwrap_or(&[]),
+        t.file_stats.as_deref().unwrap_or(&[]),
+    );
+    let items = files
+        .iter()
+        .map(|x| {
+            let name = format!(
+                "{}{}{}",
+                "  ".repeat(x.depth),
+                x.name,
+                if x.isDir { "/" } else { "" }
+            );
+            let style = if x.wanted {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            vec![
+                Cell::from(Span::styled(name, style)),
+                Cell::from(Span::raw(format!("{:.3}GB", x.length as f32 / 1e9))),
+                Cell::from(Span::raw(percent(x.done, x.length))),
+            ]
+        })
+        .collect();
+    f.render_widget(table("Files", items, &FILE_WIDTHS), chunks[1]);
+
+    let items = t
+        .peers
+        .iter()
+        .flatten()
+        .map(|x| {
+            vec![
+                Cell::from(Span::raw(x.address.to_owned())),
+                Cell::from(Span::raw(x.client_name.to_owned())),
+                Cell::from(Span::raw(format!(
+                    "{} {:.1}%",
+                    x.flag_str,
+                    x.progress * 100.0
+                ))),
+                Cell::from(Span::raw(format!(
+                    "Down {:.0}KB/s",
+                    x.rate_to_client as f32 / 1024.0
+                ))),
+                Cell::from(Span::raw(format!(
+                    "Up {:.0}KB/s",
+                    x.rate_to_peer as f32 / 1024.0
+                ))),
+            ]
+        })
+        .collect();
+    f.render_widget(table("Peers", items, &PEER_WIDTHS), chunks[2]);
+
+    let items = t
+        .tracker_stats
+        .iter()
+        .flatten()
+        .map(|x| {
+            let result = if x.last_announce_succeeded {
+                Span::styled("ok".to_string(), Style::default().fg(Color::Green))
+            } else if x.last_announce_time > 0 {
+                Span::styled(
+                    x.last_announce_result.to_owned(),
+                    Style::default().fg(Color::Red),
+                )
+            } else {
+                Span::raw("not announced".to_string())
+            };
+            vec![
+                Cell::from(Span::raw(x.host.to_owned())),
+                Cell::from(result),
+                Cell::from(Span::raw(format!(
+                    "S {} L {}",
+                    x.seeder_count.max(0),
+                    x.leecher_count.max(0)
+                ))),
+                Cell::from(Span::raw(format!("Next {}", time(x.next_announce_time)))),
+            ]
+        })
+        .collect();
+    f.render_widget(table("Trackers", items, &TRACKER_WIDTHS), chunks[3]);
+}
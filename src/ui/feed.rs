@@ -0,0 +1,198 @@
+use crossterm::event::KeyCode;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::tasks::promote::{FeedEntry, Verdict};
+use crate::u2client::types::RssInfo;
+use crate::ui::actions::drawConfirm;
+
+/// rows PageUp and PageDown move the selection by
+const PAGE: usize = 10;
+
+const WIDTHS: [Constraint; 8] = [
+    Constraint::Percentage(32),
+    Constraint::Percentage(8),
+    Constraint::Percentage(8),
+    Constraint::Percentage(8),
+    Constraint::Percentage(8),
+    Constraint::Percentage(6),
+    Constraint::Percentage(6),
+    Constraint::Percentage(24),
+];
+
+/// what a key of the feed tab asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedAction {
+    Add,
+    Magic,
+}
+
+/// an action on one feed item
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedCommand {
+    pub item: RssInfo,
+    pub action: FeedAction,
+}
+
+impl FeedCommand {
+    pub fn describe(&self) -> String {
+        match self.action {
+            FeedAction::Add => format!("add {}", self.item.title),
+            FeedAction::Magic => format!("cast free on {}", self.item.title),
+        }
+    }
+}
+
+/// the selection of the feed tab
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedView {
+    /// uid of the selected item, so it stays selected when the feed is refreshed
+    pub selected: Option<String>,
+    /// a magic waiting for y or n, it spends UCoin
+    pub confirm: Option<FeedCommand>,
+    /// a command ready to be sent, taken by the caller
+    pub command: Option<FeedCommand>,
+}
+
+impl FeedView {
+    /// the index of the selection in `entries`, the first one if it is gone
+    pub fn position(&self, entries: &[FeedEntry]) -> Option<usize> {
+        if entries.is_empty() {
+            return None;
+        }
+        Some(
+            self.selected
+                .as_ref()
+                .and_then(|x| entries.iter().position(|y| &y.item.uid == x))
+                .unwrap_or(0),
+        )
+    }
+
+    fn moveBy(&mut self, entries: &[FeedEntry], by: i64) {
+        if let Some(now) = self.position(entries) {
+            let to = (now as i64 + by).clamp(0, entries.len() as i64 - 1) as usize;
+            self.selected = Some(entries[to].item.uid.to_owned());
+        }
+    }
+
+    /// false leaves the key to the caller
+    pub fn handleKey(&mut self, key: KeyCode, entries: &[FeedEntry]) -> bool {
+        if let Some(x) = self.confirm.take() {
+            match key {
+                KeyCode::Char('y') | KeyCode::Enter => self.command = Some(x),
+                KeyCode::Char('n') | KeyCode::Esc => {}
+                _ => self.confirm = Some(x),
+            }
+            return true;
+        }
+        match key {
+            KeyCode::Up => self.moveBy(entries, -1),
+            KeyCode::Down => self.moveBy(entries, 1),
+            KeyCode::PageUp => self.moveBy(entries, -(PAGE as i64)),
+            KeyCode::PageDown => self.moveBy(entries, PAGE as i64),
+            KeyCode::Home => self.moveBy(entries, -(entries.len() as i64)),
+            KeyCode::End => self.moveBy(entries, entries.len() as i64),
+            KeyCode::Char('a') => {
+                // already in the client, nothing to add
+                if let Some(i) = self.position(entries) {
+                    if entries[i].verdict != Verdict::Present {
+                        self.command = Some(FeedCommand {
+                            item: entries[i].item.clone(),
+                            action: FeedAction::Add,
+                        });
+                    }
+                }
+            }
+            KeyCode::Char('m') => {
+                if let Some(i) = self.position(entries) {
+                    self.confirm = Some(FeedCommand {
+                        item: entries[i].item.clone(),
+                        action: FeedAction::Magic,
+                    });
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// what the feed tab draws
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedTable {
+    pub entries: Vec<FeedEntry>,
+    pub view: FeedView,
+}
+
+fn verdictStyle(x: &Verdict) -> Style {
+    match x {
+        Verdict::Added { .. } => Style::default().fg(Color::Green),
+        Verdict::Failed(_) => Style::default().fg(Color::Red),
+        Verdict::Present => Style::default().fg(Color::DarkGray),
+        Verdict::Skipped(_) => Style::default(),
+    }
+}
+
+pub fn drawFeed<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<FeedTable>) {
+    let x = match x {
+        Some(x) => x,
+        None => {
+            let table = Table::new(vec![Row::new(vec![Cell::from(Span::raw(
+                "waiting for the first promote cycle",
+            ))])])
+            .block(Block::default().title("Feed").borders(Borders::ALL))
+            .widths(&[Constraint::Percentage(100)]);
+            f.render_widget(table, area);
+            return;
+        }
+    };
+    let header = Row::new(
+        [
+            "Title", "Category", "Size", "FX", "S/L", "Avg", "Score", "Decision",
+        ]
+        .iter()
+        .map(|x| Cell::from(Span::raw(*x))),
+    );
+    let items: Vec<Row> = x
+        .entries
+        .iter()
+        .map(|x| {
+            let info = &x.item.U2Info;
+            Row::new(vec![
+                Cell::from(Span::raw(x.item.title.to_owned())),
+                Cell::from(Span::raw(x.item.cat.to_owned())),
+                Cell::from(Span::raw(format!("{:.2}GiB", info.GbSize))),
+                Cell::from(Span::raw(format!(
+                    "{}x/{:.0}%",
+                    info.uploadFX,
+                    info.downloadFX * 100.0
+                ))),
+                Cell::from(Span::raw(format!("{}/{}", info.seeder, info.leecher))),
+                Cell::from(Span::raw(format!("{:.0}%", info.avgProgress * 100.0))),
+                Cell::from(Span::raw(match x.score {
+                    Some(x) => format!("{:.2}", x),
+                    None => "-".to_string(),
+                })),
+                Cell::from(Span::styled(x.verdict.describe(), verdictStyle(&x.verdict))),
+            ])
+        })
+        .collect();
+    let title = format!("Feed {} a add m magic", x.entries.len());
+    let table = Table::new(items)
+        .header(header)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&WIDTHS);
+    let mut state = TableState::default();
+    state.select(x.view.position(&x.entries));
+    f.render_stateful_widget(table, area, &mut state);
+    if let Some(ref x) = x.view.confirm {
+        drawConfirm(f, area, &x.describe());
+    }
+}
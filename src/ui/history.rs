@@ -0,0 +1,342 @@
+use std::collections::VecDeque;
+
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    symbols,
+    text::Span,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType},
+    Frame,
+};
+
+use crate::store::SessionSample;
+use crate::torrentLib::response::SessionStats;
+use crate::u2client::client::U2client;
+use crate::u2client::types::UserInfo;
+
+/// seconds of samples kept in memory, longer windows come from the store
+pub const RETAIN: i64 = 3600;
+/// points a graph is reduced to
+const BUCKETS: i64 = 120;
+
+/// the span a graph covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    FiveMinutes,
+    Hour,
+    Day,
+    Week,
+}
+
+impl Window {
+    pub fn seconds(&self) -> i64 {
+        match self {
+            Window::FiveMinutes => 300,
+            Window::Hour => 3600,
+            Window::Day => 86400,
+            Window::Week => 604800,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Window::FiveMinutes => "5m",
+            Window::Hour => "1h",
+            Window::Day => "24h",
+            Window::Week => "7d",
+        }
+    }
+
+    pub fn next(&self) -> Window {
+        match self {
+            Window::FiveMinutes => Window::Hour,
+            Window::Hour => Window::Day,
+            Window::Day => Window::Week,
+            Window::Week => Window::FiveMinutes,
+        }
+    }
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window::FiveMinutes
+    }
+}
+
+/// transmission speeds in bytes per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedSample {
+    pub time: i64,
+    pub upload: f64,
+    pub download: f64,
+}
+
+impl SpeedSample {
+    pub fn new(time: i64, x: &SessionStats) -> SpeedSample {
+        SpeedSample {
+            time,
+            upload: x.uploadSpeed as f64,
+            download: x.downloadSpeed as f64,
+        }
+    }
+}
+
+impl From<&SessionSample> for SpeedSample {
+    fn from(x: &SessionSample) -> Self {
+        SpeedSample {
+            time: x.time,
+            upload: x.uploadSpeed as f64,
+            download: x.downloadSpeed as f64,
+        }
+    }
+}
+
+/// the totals U2 shows, in GiB
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RemoteSample {
+    pub time: i64,
+    pub upload: f64,
+    pub download: f64,
+    pub ratio: f64,
+}
+
+impl RemoteSample {
+    /// `None` if the page did not give numbers, e.g. an infinite ratio
+    pub fn new(time: i64, x: &UserInfo) -> Option<RemoteSample> {
+        Some(RemoteSample {
+            time,
+            upload: U2client::toGbSize(&x.upload).ok()? as f64,
+            download: U2client::toGbSize(&x.download).ok()? as f64,
+            ratio: x.shareRate.trim().parse().ok()?,
+        })
+    }
+}
+
+/// the recent samples in memory, oldest first
+#[derive(Debug, Default)]
+pub struct History {
+    speeds: VecDeque<SpeedSample>,
+    remote: VecDeque<RemoteSample>,
+}
+
+fn trim<T>(x: &mut VecDeque<T>, time: impl Fn(&T) -> i64, now: i64) {
+    while matches!(x.front(), Some(y) if time(y) <= now - RETAIN) {
+        x.pop_front();
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    pub fn pushSession(&mut self, time: i64, x: &SessionStats) {
+        self.speeds.push_back(SpeedSample::new(time, x));
+        trim(&mut self.speeds, |x| x.time, time);
+    }
+
+    pub fn pushRemote(&mut self, time: i64, x: &UserInfo) {
+        if let Some(x) = RemoteSample::new(time, x) {
+            self.remote.push_back(x);
+        }
+        trim(&mut self.remote, |x| x.time, time);
+    }
+
+    /// the time since which both series are in memory, `None` while either is empty
+    pub fn coveredSince(&self) -> Option<i64> {
+        Some(self.speeds.front()?.time.max(self.remote.front()?.time))
+    }
+
+    /// the graphs of `window` up to `now`, `session` and `user` being the samples stored
+    /// since the window began; stored samples are used only before the ones in memory
+    pub fn graph(
+        &self,
+        window: Window,
+        now: i64,
+        session: &[SessionSample],
+        user: &[(i64, UserInfo)],
+    ) -> HistoryGraph {
+        let start = now - window.seconds();
+        let first = self.speeds.front().map(|x| x.time).unwrap_or(i64::MAX);
+        let speeds: Vec<SpeedSample> = session
+            .iter()
+            .filter(|x| x.time < first)
+            .map(SpeedSample::from)
+            .chain(self.speeds.iter().copied())
+            .filter(|x| x.time > start && x.time <= now)
+            .collect();
+        let first = self.remote.front().map(|x| x.time).unwrap_or(i64::MAX);
+        let remote: Vec<RemoteSample> = user
+            .iter()
+            .filter(|x| x.0 < first)
+            .filter_map(|(time, x)| RemoteSample::new(*time, x))
+            .chain(self.remote.iter().copied())
+            .filter(|x| x.time > start && x.time <= now)
+            .collect();
+        let (upload0, download0) = remote
+            .first()
+            .map(|x| (x.upload, x.download))
+            .unwrap_or((0.0, 0.0));
+
+        let series = |points: Vec<(i64, f64)>| buckets(&points, start, window.seconds());
+        HistoryGraph {
+            window,
+            upload: series(speeds.iter().map(|x| (x.time, x.upload)).collect()),
+            download: series(speeds.iter().map(|x| (x.time, x.download)).collect()),
+            remoteUpload: series(
+                remote
+                    .iter()
+                    .map(|x| (x.time, x.upload - upload0))
+                    .collect(),
+            ),
+            remoteDownload: series(
+                remote
+                    .iter()
+                    .map(|x| (x.time, x.download - download0))
+                    .collect(),
+            ),
+            ratio: series(remote.iter().map(|x| (x.time, x.ratio)).collect()),
+        }
+    }
+}
+
+/// `points` averaged into `BUCKETS` buckets, x being seconds since `start`; empty buckets are left out
+fn buckets(points: &[(i64, f64)], start: i64, span: i64) -> Vec<(f64, f64)> {
+    let width = (span / BUCKETS).max(1);
+    let mut sum = vec![(0.0, 0); BUCKETS as usize];
+    for (time, value) in points.iter() {
+        let i = ((time - start - 1).max(0) / width).min(BUCKETS - 1) as usize;
+        sum[i].0 += value;
+        sum[i].1 += 1;
+    }
+    sum.into_iter()
+        .enumerate()
+        .filter(|(_, (_, n))| *n > 0)
+        .map(|(i, (total, n))| ((i as i64 * width + width) as f64, total / n as f64))
+        .collect()
+}
+
+/// what the BT tab plots
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryGraph {
+    pub window: Window,
+    /// bytes per second
+    pub upload: Vec<(f64, f64)>,
+    pub download: Vec<(f64, f64)>,
+    /// GiB gained on U2 since the window began
+    pub remoteUpload: Vec<(f64, f64)>,
+    pub remoteDownload: Vec<(f64, f64)>,
+    pub ratio: Vec<(f64, f64)>,
+}
+
+fn top(x: &[&[(f64, f64)]]) -> f64 {
+    x.iter()
+        .flat_map(|x| x.iter())
+        .map(|x| x.1)
+        .fold(0.0, f64::max)
+}
+
+fn chart<'a>(
+    title: String,
+    window: Window,
+    data: Vec<(&'a str, &'a [(f64, f64)], Color)>,
+    (low, high): (f64, f64),
+    label: impl Fn(f64) -> String,
+) -> Chart<'a> {
+    let high = if high > low { high } else { low + 1.0 };
+    let datasets = data
+        .into_iter()
+        .map(|(name, data, color)| {
+            Dataset::default()
+                .name(name)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(color))
+                .data(data)
+        })
+        .collect();
+    Chart::new(datasets)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, window.seconds() as f64])
+                .labels(vec![
+                    Span::raw(format!("-{}", window.title())),
+                    Span::raw("now"),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([low, high])
+                .labels(vec![Span::raw(label(low)), Span::raw(label(high))]),
+        )
+}
+
+pub fn drawHistory<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<HistoryGraph>) {
+    let x = match x {
+        Some(x) => x,
+        None => {
+            f.render_widget(
+                Block::default().title("History").borders(Borders::ALL),
+                area,
+            );
+            return;
+        }
+    };
+    let chunks = Layout::default()
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .direction(Direction::Horizontal)
+        .split(area);
+
+    let high = top(&[&x.upload, &x.download]);
+    f.render_widget(
+        chart(
+            format!("Speed {} (w window)", x.window.title()),
+            x.window,
+            vec![
+                ("upload", &x.upload, Color::Green),
+                ("download", &x.download, Color::Cyan),
+            ],
+            (0.0, high),
+            |x| format!("{:.2}MB/s", x / 1e6),
+        ),
+        chunks[0],
+    );
+
+    let high = top(&[&x.remoteUpload, &x.remoteDownload]);
+    f.render_widget(
+        chart(
+            format!("U2 transfer {}", x.window.title()),
+            x.window,
+            vec![
+                ("upload", &x.remoteUpload, Color::Green),
+                ("download", &x.remoteDownload, Color::Cyan),
+            ],
+            (0.0, high),
+            |x| format!("{:.1}GiB", x),
+        ),
+        chunks[1],
+    );
+
+    let low = x.ratio.iter().map(|x| x.1).fold(f64::MAX, f64::min);
+    let low = if low == f64::MAX { 0.0 } else { low };
+    let high = top(&[&x.ratio]);
+    f.render_widget(
+        chart(
+            format!("Ratio {}", x.window.title()),
+            x.window,
+            vec![("ratio", &x.ratio, Color::Yellow)],
+            (low, high),
+            |x| format!("{:.3}", x),
+        ),
+        chunks[2],
+    );
+}
@@ -0,0 +1,270 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crossterm::event::KeyCode;
+use log::Level;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Row, Table},
+    Frame,
+};
+
+use crate::ui::cycle;
+
+/// lines PageUp and PageDown scroll by
+const PAGE: usize = 20;
+/// lines handed to the dashboard from the scroll position on, more than any screen shows
+const VISIBLE: usize = 200;
+/// the level filters `l` goes through after showing every level
+const LEVELS: [Level; 3] = [Level::Error, Level::Warn, Level::Info];
+
+/// a line of main.log, `{d(%Y-%m-%d %H:%M:%S)} {l} - {m}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub time: String,
+    /// lines written before the level was logged count as info
+    pub level: Level,
+    /// the word before the first colon, like `promote` or `backEnd`
+    pub task: Option<String>,
+    pub text: String,
+}
+
+impl LogLine {
+    pub fn parse(x: &str) -> LogLine {
+        let (time, rest) = match x.find(" - ") {
+            Some(i) => (&x[..i], &x[i + 3..]),
+            None => ("", x),
+        };
+        let (time, level) = match time.rfind(' ') {
+            Some(i) => match Level::from_str(&time[i + 1..]) {
+                Ok(level) => (&time[..i], level),
+                Err(_) => (time, Level::Info),
+            },
+            None => (time, Level::Info),
+        };
+        let task = rest
+            .find(':')
+            .map(|i| &rest[..i])
+            .filter(|x| !x.is_empty() && x.chars().all(|c| c.is_ascii_alphanumeric()))
+            .map(|x| x.to_string());
+        LogLine {
+            time: time.to_string(),
+            level,
+            task,
+            text: rest.to_string(),
+        }
+    }
+}
+
+/// scrolling, filters and search of the log tab
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LogView {
+    /// index of the top line among the filtered ones, 0 being the newest
+    pub offset: usize,
+    /// the least severe level shown
+    pub level: Option<Level>,
+    pub task: Option<String>,
+    /// highlighted, ignoring case
+    pub search: String,
+    /// whether keys go to `search`
+    pub editing: bool,
+}
+
+impl LogView {
+    /// the lines passing the filters, newest first like `lines`
+    pub fn apply<'a>(&self, lines: &'a [LogLine]) -> Vec<&'a LogLine> {
+        lines
+            .iter()
+            .filter(|x| !matches!(self.level, Some(y) if x.level > y))
+            .filter(|x| self.task.is_none() || x.task == self.task)
+            .collect()
+    }
+
+    fn matches(&self, x: &LogLine) -> bool {
+        !self.search.is_empty() && x.text.to_lowercase().contains(&self.search.to_lowercase())
+    }
+
+    fn scrollBy(&mut self, len: usize, by: i64) {
+        let last = len.saturating_sub(1) as i64;
+        self.offset = (self.offset as i64 + by).clamp(0, last) as usize;
+    }
+
+    /// handles a key meant for the log tab, false leaves it to the caller
+    pub fn handleKey(&mut self, key: KeyCode, lines: &[LogLine]) -> bool {
+        if self.editing {
+            match key {
+                KeyCode::Char(x) => self.search.push(x),
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Enter => self.editing = false,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.editing = false;
+                }
+                _ => return false,
+            }
+            return true;
+        }
+        let shown = self.apply(lines);
+        match key {
+            KeyCode::Up => self.scrollBy(shown.len(), -1),
+            KeyCode::Down => self.scrollBy(shown.len(), 1),
+            KeyCode::PageUp => self.scrollBy(shown.len(), -(PAGE as i64)),
+            KeyCode::PageDown => self.scrollBy(shown.len(), PAGE as i64),
+            KeyCode::Home => self.offset = 0,
+            KeyCode::End => self.offset = shown.len().saturating_sub(1),
+            KeyCode::Char('l') => {
+                self.level = cycle(&self.level, &LEVELS);
+                self.offset = 0;
+            }
+            KeyCode::Char('t') => {
+                let all: BTreeSet<&String> = lines.iter().filter_map(|x| x.task.as_ref()).collect();
+                let all: Vec<String> = all.into_iter().cloned().collect();
+                self.task = cycle(&self.task, &all);
+                self.offset = 0;
+            }
+            KeyCode::Char('/') => self.editing = true,
+            // the next older match, from the top again after the last
+            KeyCode::Char('n') => {
+                let next = shown
+                    .iter()
+                    .enumerate()
+                    .skip(self.offset + 1)
+                    .chain(shown.iter().enumerate().take(self.offset + 1))
+                    .find(|(_, x)| self.matches(x));
+                if let Some((i, _)) = next {
+                    self.offset = i;
+                }
+            }
+            KeyCode::Esc => *self = LogView::default(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// what the log tab draws
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogTable {
+    /// the filtered lines from the scroll position on
+    pub lines: Vec<LogLine>,
+    /// lines passing the filters and all of them
+    pub shown: usize,
+    pub total: usize,
+    pub view: LogView,
+}
+
+impl LogTable {
+    pub fn new(all: &[LogLine], view: &LogView) -> LogTable {
+        let shown = view.apply(all);
+        let offset = view.offset.min(shown.len().saturating_sub(1));
+        LogTable {
+            lines: shown
+                .iter()
+                .skip(offset)
+                .take(VISIBLE)
+                .map(|x| (*x).clone())
+                .collect(),
+            shown: shown.len(),
+            total: all.len(),
+            view: LogView {
+                offset,
+                ..view.clone()
+            },
+        }
+    }
+}
+
+fn levelStyle(x: Level) -> Style {
+    match x {
+        Level::Error => Style::default().fg(Color::Red),
+        Level::Warn => Style::default().fg(Color::Yellow),
+        _ => Style::default(),
+    }
+}
+
+/// `text` with every match of `search` highlighted
+fn highlight<'a>(text: &'a str, search: &str, style: Style) -> Spans<'a> {
+    let mark = Style::default()
+        .bg(Color::Yellow)
+        .fg(Color::Black)
+        .add_modifier(Modifier::BOLD);
+    let lower = text.to_lowercase();
+    let search = search.to_lowercase();
+    // lowering may move byte offsets, then there is nothing safe to mark
+    if search.is_empty() || lower.len() != text.len() {
+        return Spans::from(Span::styled(text, style));
+    }
+    let mut spans = Vec::new();
+    let mut from = 0;
+    for (i, x) in lower.match_indices(&search) {
+        let end = i + x.len();
+        if i < from || !text.is_char_boundary(i) || !text.is_char_boundary(end) {
+            continue;
+        }
+        spans.push(Span::styled(&text[from..i], style));
+        spans.push(Span::styled(&text[i..end], mark));
+        from = end;
+    }
+    spans.push(Span::styled(&text[from..], style));
+    Spans::from(spans)
+}
+
+pub fn drawLog<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<LogTable>) {
+    let x = match x {
+        Some(x) => x,
+        None => {
+            let table = Table::new(vec![Row::new(vec![Cell::from(Span::raw(
+                "failed to get log",
+            ))])])
+            .block(Block::default().title("Logs").borders(Borders::ALL))
+            .widths(&[Constraint::Percentage(100)]);
+            f.render_widget(table, area);
+            return;
+        }
+    };
+    let view = &x.view;
+    let mut title = format!("Logs {}/{}", x.shown, x.total);
+    if x.shown > 0 {
+        title.push_str(&format!(" from {}", view.offset + 1));
+    }
+    if let Some(level) = view.level {
+        title.push_str(&format!(" [{} and worse]", level));
+    }
+    if let Some(ref task) = view.task {
+        title.push_str(&format!(" [task {}]", task));
+    }
+    if view.editing || !view.search.is_empty() {
+        title.push_str(&format!(
+            " [/{}{}]",
+            view.search,
+            if view.editing { "_" } else { "" }
+        ));
+    }
+    title.push_str(" PgUp/PgDn scroll Home/End jump l level t task / search n next Esc clear");
+
+    let items: Vec<Row> = x
+        .lines
+        .iter()
+        .map(|x| {
+            let style = levelStyle(x.level);
+            Row::new(vec![
+                Cell::from(Span::raw(x.time.to_owned())),
+                Cell::from(Span::styled(x.level.to_string(), style)),
+                Cell::from(highlight(&x.text, &view.search, style)),
+            ])
+        })
+        .collect();
+    let table = Table::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(19),
+            Constraint::Length(5),
+            Constraint::Percentage(100),
+        ]);
+    f.render_widget(table, area);
+}
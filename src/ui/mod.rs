@@ -1,9 +1,6 @@
-use std::fs::File;
-use std::io::BufReader;
 use std::option::Option::Some;
 
-use rev_lines::RevLines;
-use sysinfo::{ComponentExt, DiskExt, NetworkExt, NetworksExt, ProcessorExt, System, SystemExt};
+use chrono::{Local, TimeZone};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -13,28 +10,45 @@ use tui::{
     Frame,
 };
 
+use crate::scheduler::{JobState, JobStatus};
 use crate::torrentLib::response::{SessionStats, Stats};
-use crate::u2client::types::{Status, UserInfo};
+use crate::u2client::cache::CacheStats;
+use crate::u2client::types::{Status, UCoinSummary, UserInfo};
+
+use self::actions::drawToast;
+use self::feed::drawFeed;
+use self::history::{drawHistory, HistoryGraph};
+use self::log::drawLog;
+use self::sampler::HardwareSample;
+use self::torrents::drawTorrents;
+
+pub mod actions;
+pub mod detail;
+pub mod feed;
+pub mod history;
+pub mod log;
+pub mod sampler;
+pub mod torrents;
 
 pub struct TabsState {
     pub index: usize,
 }
 
-const TITLE: &[&str] = &["Status", "BT", "LOG"];
+const TITLE: &[&str] = &["Status", "BT", "Torrents", "Feed", "LOG"];
 
 impl TabsState {
     pub fn new() -> TabsState {
         TabsState { index: 0 }
     }
     pub fn next(&mut self) {
-        self.index = (self.index + 1) % 3;
+        self.index = (self.index + 1) % TITLE.len();
     }
 
     pub fn previous(&mut self) {
         if self.index > 0 {
             self.index -= 1;
         } else {
-            self.index = 2;
+            self.index = TITLE.len() - 1;
         }
     }
 }
@@ -45,6 +59,48 @@ impl Default for TabsState {
     }
 }
 
+/// a table built from shared data, rebuilt only when `key`, the generation of the data
+/// and the view of it, changed since the last build
+pub struct Cached<K, T> {
+    key: Option<K>,
+    value: Option<T>,
+}
+
+impl<K: PartialEq, T: Clone> Cached<K, T> {
+    pub fn new() -> Cached<K, T> {
+        Cached {
+            key: None,
+            value: None,
+        }
+    }
+
+    pub fn get(&mut self, key: K, build: impl FnOnce() -> Option<T>) -> Option<T> {
+        if self.key.as_ref() != Some(&key) {
+            self.value = build();
+            self.key = Some(key);
+        }
+        self.value.clone()
+    }
+}
+
+impl<K: PartialEq, T: Clone> Default for Cached<K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// the value after `x` in `all`, going from the last back to `None`
+pub fn cycle<T: Clone + PartialEq>(x: &Option<T>, all: &[T]) -> Option<T> {
+    match x {
+        None => all.first().cloned(),
+        Some(x) => all
+            .iter()
+            .position(|y| y == x)
+            .and_then(|i| all.get(i + 1))
+            .cloned(),
+    }
+}
+
 pub fn draw<B: Backend>(f: &mut Frame<B>, x: Status, mask: u8, idx: usize) {
     let chunks = Layout::default()
         .constraints([Constraint::Percentage(10), Constraint::Percentage(90)].as_ref())
@@ -65,13 +121,22 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, x: Status, mask: u8, idx: usize) {
         })
         .collect()];
 
+    let mut title = "Tab".to_string();
+    if x.dryRun {
+        title.push_str(" (dry run)");
+    }
+    if let Some(x) = x.sitePaused {
+        title.push_str(&format!(" (site down, retry in {}s)", x));
+    }
     let items: Vec<Row> = items.into_iter().map(Row::new).collect();
     let table = Table::new(items)
-        .block(Block::default().title("Tab").borders(Borders::ALL))
+        .block(Block::default().title(title).borders(Borders::ALL))
         .widths(&[
             Constraint::Percentage(8),
             Constraint::Percentage(8),
             Constraint::Percentage(8),
+            Constraint::Percentage(8),
+            Constraint::Percentage(8),
         ]);
     f.render_widget(table, chunks[0]);
 
@@ -79,39 +144,40 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, x: Status, mask: u8, idx: usize) {
     match idx {
         0 => {
             let chunks = Layout::default()
-                .constraints([Constraint::Percentage(20), Constraint::Percentage(40)].as_ref())
+                .constraints(
+                    [
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(25),
+                        Constraint::Percentage(45),
+                    ]
+                    .as_ref(),
+                )
                 .direction(Direction::Vertical)
                 .split(area);
 
             drawRemoteInfo(f, chunks[0], &x.remote, (mask >> 1) & 1);
-            drawHardwareInfo(f, chunks[1], &x.hardware);
+            drawUCoin(f, chunks[1], &x.ucoin);
+            drawJobs(f, chunks[2], &x.jobs, &x.cache);
+            drawHardwareInfo(f, chunks[3], &x.hardware);
         }
         1 => {
-            drawLocalInfo(f, area, &x.local, mask & 1);
+            drawLocalInfo(f, area, &x.local, &x.history, mask & 1);
         }
         2 => {
-            drawLog(f, area, &x.logDir);
+            drawTorrents(f, area, &x.torrents, &x.detail);
+        }
+        3 => {
+            drawFeed(f, area, &x.feed);
+        }
+        4 => {
+            drawLog(f, area, &x.log);
         }
         _ => {}
     }
-}
-
-fn drawLog<B: Backend>(f: &mut Frame<B>, area: Rect, log: &Option<String>) {
-    let F = |x: &Option<String>| -> crate::Result<Vec<Row>> {
-        let x = x.as_ref().ok_or("")?;
-        let file = File::open(x)?;
-        let rev_lines = RevLines::new(BufReader::new(file))?;
-        Ok(rev_lines
-            .take(25)
-            .map(|x| Row::new(vec![Cell::from(Span::raw(x))]))
-            .collect::<Vec<Row>>())
-    };
-    let items =
-        F(log).unwrap_or_else(|_| vec![Row::new(vec![Cell::from(Span::raw("failed to get log"))])]);
-    let table = Table::new(items)
-        .block(Block::default().title("Logs").borders(Borders::ALL))
-        .widths(&[Constraint::Percentage(100)]);
-    f.render_widget(table, area);
+    if let Some(ref toast) = x.toast {
+        drawToast(f, area, toast);
+    }
 }
 
 fn splitTime(mut time: u64) -> (u64, u64, u64, u64) {
@@ -188,20 +254,110 @@ fn drawRemoteInfo<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<UserInfo>
     f.render_widget(table, area);
 }
 
-fn drawHardwareInfo<B: Backend>(f: &mut Frame<B>, area: Rect, sys: &Option<System>) {
+fn drawUCoin<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<UCoinSummary>) {
+    let items: Vec<Vec<Cell>> = match x {
+        Some(x) => {
+            vec![vec![
+                Cell::from(Span::styled(
+                    format!("Income {:.2}", x.income),
+                    Style::default().fg(Color::Green),
+                )),
+                Cell::from(Span::styled(
+                    format!("Magic {:.2}", x.magic),
+                    Style::default().fg(Color::Red),
+                )),
+                Cell::from(Span::styled(
+                    format!("Net {:.2}", x.net),
+                    Style::default().fg(Color::Yellow),
+                )),
+                Cell::from(Span::raw(match x.hourly {
+                    Some(h) => format!("{:.2}/hour", h),
+                    None => "".to_string(),
+                })),
+            ]]
+        }
+        None => {
+            vec![vec![Cell::from(Span::raw("loading".to_string()))]]
+        }
+    };
+
+    let items: Vec<Row> = items.into_iter().map(Row::new).collect();
+    let table = Table::new(items)
+        .block(Block::default().title("UCoin today").borders(Borders::ALL))
+        .widths(&[
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+        ]);
+    f.render_widget(table, area);
+}
+
+fn drawJobs<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    x: &Option<Vec<JobStatus>>,
+    cache: &Option<CacheStats>,
+) {
+    let time = |x: Option<i64>| match x {
+        Some(x) => Local.timestamp(x, 0).format("%m-%d %H:%M:%S").to_string(),
+        None => "-".to_string(),
+    };
+    let items: Vec<Vec<Cell>> = match x {
+        Some(x) => x
+            .iter()
+            .map(|x| {
+                let color = match x.state {
+                    JobState::Done => Color::Green,
+                    JobState::Running | JobState::Waiting => Color::Yellow,
+                    _ => Color::Red,
+                };
+                vec![
+                    Cell::from(Span::raw(x.name.to_owned())),
+                    Cell::from(Span::styled(
+                        x.state.to_string(),
+                        Style::default().fg(color),
+                    )),
+                    Cell::from(Span::raw(format!("last {}", time(x.lastRun)))),
+                    Cell::from(Span::raw(format!("next {}", time(x.nextRun)))),
+                    Cell::from(Span::raw(format!("runs {}/{} failed", x.runs, x.failures))),
+                ]
+            })
+            .collect(),
+        None => {
+            vec![vec![Cell::from(Span::raw("loading".to_string()))]]
+        }
+    };
+
+    let items: Vec<Row> = items.into_iter().map(Row::new).collect();
+    let table = Table::new(items)
+        .block(
+            Block::default()
+                .title(match cache {
+                    Some(x) => format!("Jobs (cache {} saved, {} miss)", x.saved(), x.misses),
+                    None => "Jobs".to_string(),
+                })
+                .borders(Borders::ALL),
+        )
+        .widths(&[
+            Constraint::Percentage(12),
+            Constraint::Percentage(28),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+        ]);
+    f.render_widget(table, area);
+}
+
+fn drawHardwareInfo<B: Backend>(f: &mut Frame<B>, area: Rect, sys: &Option<HardwareSample>) {
+    let unknown = |x: &Option<String>| x.to_owned().unwrap_or_else(|| "<unknown>".to_owned());
     let items: Vec<Vec<Cell>> = match sys {
         Some(sys) => {
-            let firstCore = sys.get_processors().get(0);
-            let brand = if let Some(firstCore) = firstCore {
-                firstCore.get_brand()
-            } else {
-                "Unknown CPU"
-            };
-            let (days, hours, minutes, secs) = splitTime(sys.get_uptime());
+            let (days, hours, minutes, secs) = splitTime(sys.uptime);
 
             let mut items = vec![
                 vec![
-                    Cell::from(Span::raw(brand.to_string())),
+                    Cell::from(Span::raw(sys.cpu.to_owned())),
                     Cell::from(Span::raw(format!(
                         "{} days {} hours {} mins {}",
                         days, hours, minutes, secs
@@ -210,110 +366,63 @@ fn drawHardwareInfo<B: Backend>(f: &mut Frame<B>, area: Rect, sys: &Option<Syste
                 vec![
                     Cell::from(Span::raw(format!(
                         "Memory {:.3}GB/{:.3}GB",
-                        sys.get_used_memory() as f32 / 1e6,
-                        sys.get_total_memory() as f32 / 1e6
+                        sys.usedMemory as f32 / 1e6,
+                        sys.totalMemory as f32 / 1e6
                     ))),
                     Cell::from(Span::raw(format!(
                         "Swap {:.3}GB/{:.3}GB",
-                        sys.get_used_swap() as f32 / 1e6,
-                        sys.get_total_swap() as f32 / 1e6
+                        sys.usedSwap as f32 / 1e6,
+                        sys.totalSwap as f32 / 1e6
                     ))),
                 ],
                 vec![
                     Cell::from(Span::raw(format!(
                         "{} {}",
-                        sys.get_name().unwrap_or_else(|| "<unknown>".to_owned()),
-                        sys.get_kernel_version()
-                            .unwrap_or_else(|| "<unknown>".to_owned())
-                    ))),
-                    Cell::from(Span::raw(format!(
-                        "Version {}",
-                        sys.get_os_version()
-                            .unwrap_or_else(|| "<unknown>".to_owned())
+                        unknown(&sys.name),
+                        unknown(&sys.kernelVersion)
                     ))),
-                    Cell::from(Span::raw(
-                        sys.get_host_name()
-                            .unwrap_or_else(|| "<unknown>".to_string()),
-                    )),
+                    Cell::from(Span::raw(format!("Version {}", unknown(&sys.osVersion)))),
+                    Cell::from(Span::raw(unknown(&sys.hostName))),
                 ],
             ];
 
-            let mut s = sys
-                .get_disks()
-                .iter()
-                .map(|i| {
-                    (
-                        i.get_mount_point().to_str().unwrap().to_string(),
-                        vec![
-                            Cell::from(Span::raw(format!(
-                                "{:?} {}",
-                                i.get_type(),
-                                i.get_mount_point().to_str().unwrap()
-                            ))),
-                            Cell::from(Span::styled(
-                                format!("Free {:.3}GB", i.get_available_space() as f32 / 1e9),
-                                Style::default().fg(Color::Yellow),
-                            )),
-                            Cell::from(Span::raw(format!(
-                                "Total {:.3}GB",
-                                i.get_total_space() as f32 / 1e9
-                            ))),
-                        ],
-                    )
-                })
-                .collect::<Vec<(String, Vec<Cell>)>>();
-
-            macro_rules! gao {
-                ($arg:tt) => {
-                    ($arg).sort_by_key(|x| x.0.to_owned());
-                    let s: Vec<Vec<Cell>> = ($arg).into_iter().map(|x| x.1).collect();
-                    items.push(vec![]);
-                    for i in s.into_iter() {
-                        items.push(i);
-                    }
-                };
+            items.push(vec![]);
+            for i in sys.disks.iter() {
+                items.push(vec![
+                    Cell::from(Span::raw(format!("{} {}", i.kind, i.mountPoint))),
+                    Cell::from(Span::styled(
+                        format!("Free {:.3}GB", i.available as f32 / 1e9),
+                        Style::default().fg(Color::Yellow),
+                    )),
+                    Cell::from(Span::raw(format!("Total {:.3}GB", i.total as f32 / 1e9))),
+                ]);
+            }
+
+            items.push(vec![]);
+            for i in sys.networks.iter() {
+                let mut name = i.name.to_owned();
+                name.truncate(20);
+                items.push(vec![
+                    Cell::from(Span::raw(name)),
+                    Cell::from(Span::raw(format!(
+                        "Download {:.3}GB",
+                        i.received as f32 / 1e9
+                    ))),
+                    Cell::from(Span::raw(format!(
+                        "Upload {:.3}GB",
+                        i.transmitted as f32 / 1e9
+                    ))),
+                ]);
+            }
+
+            items.push(vec![]);
+            for i in sys.components.iter() {
+                items.push(vec![
+                    Cell::from(Span::raw(i.label.to_owned())),
+                    Cell::from(Span::raw(format!("Temp {:.3}", i.temperature))),
+                    Cell::from(Span::raw(format!("Max {:.3}", i.max))),
+                ]);
             }
-            gao!(s);
-
-            let mut s = sys
-                .get_networks()
-                .iter()
-                .map(|(a, b)| {
-                    let mut a = a.clone();
-                    a.truncate(20);
-                    (
-                        a.to_owned(),
-                        vec![
-                            Cell::from(Span::raw(a)),
-                            Cell::from(Span::raw(format!(
-                                "Download {:.3}GB",
-                                b.get_total_received() as f32 / 1e9
-                            ))),
-                            Cell::from(Span::raw(format!(
-                                "Upload {:.3}GB",
-                                b.get_total_transmitted() as f32 / 1e9
-                            ))),
-                        ],
-                    )
-                })
-                .collect::<Vec<(String, Vec<Cell>)>>();
-            gao!(s);
-
-            let mut s = sys
-                .get_components()
-                .iter()
-                .map(|x| {
-                    (
-                        x.get_label().to_string(),
-                        vec![
-                            Cell::from(Span::raw(x.get_label().to_string())),
-                            Cell::from(Span::raw(format!("Temp {:.3}", x.get_temperature()))),
-                            Cell::from(Span::raw(format!("Max {:.3}", x.get_max()))),
-                        ],
-                    )
-                })
-                .collect::<Vec<(String, Vec<Cell>)>>();
-            gao!(s);
 
             items
         }
@@ -333,13 +442,20 @@ fn drawHardwareInfo<B: Backend>(f: &mut Frame<B>, area: Rect, sys: &Option<Syste
     f.render_widget(table, area);
 }
 
-fn drawLocalInfo<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<SessionStats>, mask: u8) {
+fn drawLocalInfo<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    x: &Option<SessionStats>,
+    history: &Option<HistoryGraph>,
+    mask: u8,
+) {
     let chunks = Layout::default()
         .constraints(
             [
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(40),
             ]
             .as_ref(),
         )
@@ -390,6 +506,7 @@ fn drawLocalInfo<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Option<SessionSta
         drawStats(f, chunks[1], &x.current_stats, "current");
         drawStats(f, chunks[2], &x.cumulative_stats, "cumulative");
     }
+    drawHistory(f, chunks[3], history);
 }
 
 fn drawStats<B: Backend>(f: &mut Frame<B>, area: Rect, x: &Stats, head: &str) {
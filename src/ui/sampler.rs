@@ -0,0 +1,206 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::time::SystemTime;
+
+use rev_lines::RevLines;
+use sysinfo::{
+    ComponentExt, DiskExt, NetworkExt, NetworksExt, ProcessorExt, RefreshKind, System, SystemExt,
+};
+
+use crate::Result;
+
+/// lines of the log the dashboard keeps
+pub const LOG_LINES: usize = 5000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSample {
+    pub kind: String,
+    pub mountPoint: String,
+    /// bytes
+    pub available: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkSample {
+    pub name: String,
+    /// bytes
+    pub received: u64,
+    pub transmitted: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentSample {
+    pub label: String,
+    pub temperature: f32,
+    pub max: f32,
+}
+
+/// what the hardware block shows, copied out of `System` so it can be shared and compared
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardwareSample {
+    pub cpu: String,
+    /// seconds
+    pub uptime: u64,
+    /// KB
+    pub usedMemory: u64,
+    pub totalMemory: u64,
+    pub usedSwap: u64,
+    pub totalSwap: u64,
+    pub name: Option<String>,
+    pub kernelVersion: Option<String>,
+    pub osVersion: Option<String>,
+    pub hostName: Option<String>,
+    pub disks: Vec<DiskSample>,
+    pub networks: Vec<NetworkSample>,
+    pub components: Vec<ComponentSample>,
+}
+
+/// one `System` for the life of the dashboard, refreshed in place
+pub struct HardwareSampler {
+    system: System,
+}
+
+impl HardwareSampler {
+    /// lists disks, networks and components once, processes are never loaded
+    pub fn new() -> HardwareSampler {
+        HardwareSampler {
+            system: System::new_with_specifics(
+                RefreshKind::new()
+                    .with_cpu()
+                    .with_memory()
+                    .with_disks_list()
+                    .with_networks_list()
+                    .with_components_list(),
+            ),
+        }
+    }
+
+    pub fn sample(&mut self) -> HardwareSample {
+        let sys = &mut self.system;
+        sys.refresh_cpu();
+        sys.refresh_memory();
+        sys.refresh_disks();
+        sys.refresh_networks();
+        sys.refresh_components();
+
+        let mut disks: Vec<DiskSample> = sys
+            .get_disks()
+            .iter()
+            .map(|x| DiskSample {
+                kind: format!("{:?}", x.get_type()),
+                mountPoint: x.get_mount_point().to_string_lossy().to_string(),
+                available: x.get_available_space(),
+                total: x.get_total_space(),
+            })
+            .collect();
+        disks.sort_by(|a, b| a.mountPoint.cmp(&b.mountPoint));
+        let mut networks: Vec<NetworkSample> = sys
+            .get_networks()
+            .iter()
+            .map(|(name, x)| NetworkSample {
+                name: name.to_owned(),
+                received: x.get_total_received(),
+                transmitted: x.get_total_transmitted(),
+            })
+            .collect();
+        networks.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut components: Vec<ComponentSample> = sys
+            .get_components()
+            .iter()
+            .map(|x| ComponentSample {
+                label: x.get_label().to_string(),
+                temperature: x.get_temperature(),
+                max: x.get_max(),
+            })
+            .collect();
+        components.sort_by(|a, b| a.label.cmp(&b.label));
+
+        HardwareSample {
+            cpu: sys
+                .get_processors()
+                .get(0)
+                .map(|x| x.get_brand().to_string())
+                .unwrap_or_else(|| "Unknown CPU".to_string()),
+            uptime: sys.get_uptime(),
+            usedMemory: sys.get_used_memory(),
+            totalMemory: sys.get_total_memory(),
+            usedSwap: sys.get_used_swap(),
+            totalSwap: sys.get_total_swap(),
+            name: sys.get_name(),
+            kernelVersion: sys.get_kernel_version(),
+            osVersion: sys.get_os_version(),
+            hostName: sys.get_host_name(),
+            disks,
+            networks,
+            components,
+        }
+    }
+}
+
+impl Default for HardwareSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// the last lines of a log file and the files it was rolled over into,
+/// read again only once the size or mtime of one of them moves
+pub struct LogTail {
+    path: String,
+    archives: Vec<String>,
+    lines: usize,
+    seen: Option<Vec<Option<(u64, Option<SystemTime>)>>>,
+}
+
+impl LogTail {
+    pub fn new(path: &str, lines: usize) -> LogTail {
+        LogTail::withArchives(path, &[], lines)
+    }
+
+    /// `archives` newest first, as `FixedWindowRoller` numbers them; missing ones are skipped
+    pub fn withArchives(path: &str, archives: &[String], lines: usize) -> LogTail {
+        LogTail {
+            path: path.to_string(),
+            archives: archives.to_vec(),
+            lines,
+            seen: None,
+        }
+    }
+
+    /// the newest lines first, `None` while no file changed
+    pub fn poll(&mut self) -> Result<Option<Vec<String>>> {
+        let meta = std::fs::metadata(&self.path)?;
+        let mut seen = vec![Some((meta.len(), meta.modified().ok()))];
+        for x in self.archives.iter() {
+            seen.push(
+                std::fs::metadata(x)
+                    .ok()
+                    .map(|meta| (meta.len(), meta.modified().ok())),
+            );
+        }
+        if Some(&seen) == self.seen.as_ref() {
+            return Ok(None);
+        }
+        let mut lines = Vec::new();
+        for (path, seen) in std::iter::once(&self.path)
+            .chain(self.archives.iter())
+            .zip(seen.iter())
+        {
+            if lines.len() >= self.lines {
+                break;
+            }
+            if seen.is_none() {
+                continue;
+            }
+            let file = File::open(path)?;
+            lines.extend(
+                RevLines::new(BufReader::new(file))?
+                    .take(self.lines - lines.len())
+                    .collect::<Vec<String>>(),
+            );
+        }
+        self.seen = Some(seen);
+        Ok(Some(lines))
+    }
+}
@@ -0,0 +1,446 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use chrono::{Local, TimeZone};
+use crossterm::event::KeyCode;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::torrentLib::response::Torrent;
+use crate::ui::actions::{self, drawConfirm, Command, TorrentCommand};
+use crate::ui::cycle;
+use crate::ui::detail::{drawDetail, TorrentDetail};
+
+/// rows PageUp and PageDown move the selection by
+const PAGE: usize = 10;
+
+/// a torrent in the client as the list shows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorrentRow {
+    pub hash: String,
+    pub name: String,
+    /// bytes
+    pub size: i64,
+    pub progress: f32,
+    pub status: i64,
+    pub ratio: f32,
+    /// bytes per second
+    pub upload: i64,
+    pub download: i64,
+    pub peers: i64,
+    pub added: i64,
+    /// (uploadFX, downloadFX) if the details of the torrent are known
+    pub fx: Option<(f32, f32)>,
+    /// the category it was added under, only known for torrents the tool added
+    pub category: Option<String>,
+}
+
+impl TorrentRow {
+    pub fn new(x: &Torrent, category: Option<String>, fx: Option<(f32, f32)>) -> TorrentRow {
+        TorrentRow {
+            hash: x.hash_string.to_owned().unwrap_or_default(),
+            name: x.name.to_owned().unwrap_or_default(),
+            size: x.total_size.unwrap_or(0),
+            progress: x.percent_done.unwrap_or(0.0),
+            status: x.status.unwrap_or(0),
+            ratio: x.upload_ratio.unwrap_or(0.0),
+            upload: x.rate_upload.unwrap_or(0),
+            download: x.rate_download.unwrap_or(0),
+            peers: x.peers_connected.unwrap_or(0),
+            added: x.added_date.unwrap_or(0),
+            fx,
+            category,
+        }
+    }
+}
+
+/// the name transmission gives a status code
+pub fn statusName(x: i64) -> &'static str {
+    match x {
+        0 => "stopped",
+        1 => "check wait",
+        2 => "checking",
+        3 => "queued",
+        4 => "downloading",
+        5 => "seed wait",
+        6 => "seeding",
+        _ => "unknown",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Size,
+    Progress,
+    Status,
+    Ratio,
+    Upload,
+    Download,
+    Peers,
+    Added,
+    FX,
+}
+
+const COLUMNS: [Column; 10] = [
+    Column::Name,
+    Column::Size,
+    Column::Progress,
+    Column::Status,
+    Column::Ratio,
+    Column::Upload,
+    Column::Download,
+    Column::Peers,
+    Column::Added,
+    Column::FX,
+];
+
+const WIDTHS: [Constraint; 10] = [
+    Constraint::Percentage(28),
+    Constraint::Percentage(8),
+    Constraint::Percentage(7),
+    Constraint::Percentage(10),
+    Constraint::Percentage(6),
+    Constraint::Percentage(9),
+    Constraint::Percentage(9),
+    Constraint::Percentage(5),
+    Constraint::Percentage(10),
+    Constraint::Percentage(8),
+];
+
+impl Column {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Size => "Size",
+            Column::Progress => "Done",
+            Column::Status => "Status",
+            Column::Ratio => "Ratio",
+            Column::Upload => "Up",
+            Column::Download => "Down",
+            Column::Peers => "Peers",
+            Column::Added => "Added",
+            Column::FX => "FX",
+        }
+    }
+
+    fn cmp(&self, a: &TorrentRow, b: &TorrentRow) -> Ordering {
+        let float = |x: f32, y: f32| x.partial_cmp(&y).unwrap_or(Ordering::Equal);
+        match self {
+            Column::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            Column::Size => a.size.cmp(&b.size),
+            Column::Progress => float(a.progress, b.progress),
+            Column::Status => a.status.cmp(&b.status),
+            Column::Ratio => float(a.ratio, b.ratio),
+            Column::Upload => a.upload.cmp(&b.upload),
+            Column::Download => a.download.cmp(&b.download),
+            Column::Peers => a.peers.cmp(&b.peers),
+            Column::Added => a.added.cmp(&b.added),
+            // the better promotion is the larger, unknown is the worst
+            Column::FX => {
+                let gain = |x: &TorrentRow| x.fx.map_or(f32::MIN, |(up, down)| up - down);
+                float(gain(a), gain(b))
+            }
+        }
+    }
+
+    fn cell(&self, x: &TorrentRow) -> String {
+        match self {
+            Column::Name => x.name.to_owned(),
+            Column::Size => format!("{:.2}GB", x.size as f32 / 1e9),
+            Column::Progress => format!("{:.1}%", x.progress * 100.0),
+            Column::Status => statusName(x.status).to_string(),
+            Column::Ratio => format!("{:.2}", x.ratio),
+            Column::Upload => speed(x.upload),
+            Column::Download => speed(x.download),
+            Column::Peers => x.peers.to_string(),
+            Column::Added => Local
+                .timestamp(x.added, 0)
+                .format("%m-%d %H:%M")
+                .to_string(),
+            Column::FX => match x.fx {
+                Some((up, down)) => format!("{}x/{:.0}%", up, down * 100.0),
+                None => "-".to_string(),
+            },
+        }
+    }
+}
+
+fn speed(x: i64) -> String {
+    if x >= 1 << 20 {
+        format!("{:.1}MB/s", x as f32 / (1 << 20) as f32)
+    } else {
+        format!("{:.0}KB/s", x as f32 / 1024.0)
+    }
+}
+
+/// sorting, filtering and the selection of the torrent list
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorrentView {
+    pub sort: Column,
+    pub descending: bool,
+    pub status: Option<i64>,
+    pub category: Option<String>,
+    /// matched against the name and hash, ignoring case
+    pub text: String,
+    /// whether keys go to `text`
+    pub editing: bool,
+    /// hash of the selected torrent, so it stays selected when rows move
+    pub selected: Option<String>,
+    /// whether the detail pane of the selected torrent is open
+    pub detail: bool,
+    /// a destructive command waiting for y or n
+    pub confirm: Option<TorrentCommand>,
+    /// a command ready to be sent, taken by the caller
+    pub command: Option<TorrentCommand>,
+}
+
+impl Default for TorrentView {
+    fn default() -> Self {
+        TorrentView {
+            sort: Column::Added,
+            descending: true,
+            status: None,
+            category: None,
+            text: String::new(),
+            editing: false,
+            selected: None,
+            detail: false,
+            confirm: None,
+            command: None,
+        }
+    }
+}
+
+impl TorrentView {
+    /// the rows passing every filter, in display order
+    pub fn apply(&self, rows: &[TorrentRow]) -> Vec<TorrentRow> {
+        let text = self.text.to_lowercase();
+        let mut ret: Vec<TorrentRow> = rows
+            .iter()
+            .filter(|x| self.status.is_none() || self.status == Some(x.status))
+            .filter(|x| self.category.is_none() || x.category == self.category)
+            .filter(|x| {
+                text.is_empty()
+                    || x.name.to_lowercase().contains(&text)
+                    || x.hash.starts_with(&text)
+            })
+            .cloned()
+            .collect();
+        ret.sort_by(|a, b| {
+            let ord = self.sort.cmp(a, b).then_with(|| a.name.cmp(&b.name));
+            if self.descending {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        ret
+    }
+
+    /// the index of the selection in `rows`, the first row if it is gone
+    pub fn position(&self, rows: &[TorrentRow]) -> Option<usize> {
+        if rows.is_empty() {
+            return None;
+        }
+        Some(
+            self.selected
+                .as_ref()
+                .and_then(|x| rows.iter().position(|y| &y.hash == x))
+                .unwrap_or(0),
+        )
+    }
+
+    fn moveBy(&mut self, rows: &[TorrentRow], by: i64) {
+        if let Some(now) = self.position(rows) {
+            let to = (now as i64 + by).clamp(0, rows.len() as i64 - 1) as usize;
+            self.selected = Some(rows[to].hash.to_owned());
+        }
+    }
+
+    /// handles a key meant for the list, `all` being every torrent before filtering;
+    /// false leaves the key to the caller
+    pub fn handleKey(&mut self, key: KeyCode, all: &[TorrentRow]) -> bool {
+        if let Some(x) = self.confirm.take() {
+            match key {
+                KeyCode::Char('y') | KeyCode::Enter => self.command = Some(x),
+                KeyCode::Char('n') | KeyCode::Esc => {}
+                _ => self.confirm = Some(x),
+            }
+            return true;
+        }
+        if self.editing {
+            match key {
+                KeyCode::Char(x) => self.text.push(x),
+                KeyCode::Backspace => {
+                    self.text.pop();
+                }
+                KeyCode::Enter => self.editing = false,
+                KeyCode::Esc => {
+                    self.text.clear();
+                    self.editing = false;
+                }
+                _ => return false,
+            }
+            return true;
+        }
+        let rows = self.apply(all);
+        match key {
+            KeyCode::Up => self.moveBy(&rows, -1),
+            KeyCode::Down => self.moveBy(&rows, 1),
+            KeyCode::PageUp => self.moveBy(&rows, -(PAGE as i64)),
+            KeyCode::PageDown => self.moveBy(&rows, PAGE as i64),
+            KeyCode::Home => self.moveBy(&rows, -(rows.len() as i64)),
+            KeyCode::End => self.moveBy(&rows, rows.len() as i64),
+            KeyCode::Char('s') => {
+                let i = COLUMNS.iter().position(|x| *x == self.sort).unwrap_or(0);
+                self.sort = COLUMNS[(i + 1) % COLUMNS.len()];
+            }
+            KeyCode::Char('S') => {
+                let i = COLUMNS.iter().position(|x| *x == self.sort).unwrap_or(0);
+                self.sort = COLUMNS[(i + COLUMNS.len() - 1) % COLUMNS.len()];
+            }
+            KeyCode::Char('r') => self.descending = !self.descending,
+            KeyCode::Char('f') => {
+                let all: BTreeSet<i64> = all.iter().map(|x| x.status).collect();
+                self.status = cycle(&self.status, &all.into_iter().collect::<Vec<i64>>());
+            }
+            KeyCode::Char('c') => {
+                let all: BTreeSet<String> = all.iter().filter_map(|x| x.category.clone()).collect();
+                self.category = cycle(&self.category, &all.into_iter().collect::<Vec<String>>());
+            }
+            KeyCode::Char('/') => self.editing = true,
+            KeyCode::Char(x) if Command::fromKey(x).is_some() => {
+                if let (Some(i), Some(command)) = (self.position(&rows), Command::fromKey(x)) {
+                    let x = TorrentCommand::new(&rows[i], command);
+                    if command.isDestructive() {
+                        self.confirm = Some(x);
+                    } else {
+                        self.command = Some(x);
+                    }
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(x) = self.position(&rows) {
+                    self.selected = Some(rows[x].hash.to_owned());
+                    self.detail = true;
+                }
+            }
+            KeyCode::Esc if self.detail => self.detail = false,
+            KeyCode::Esc => {
+                self.status = None;
+                self.category = None;
+                self.text.clear();
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// what the torrent tab draws
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorrentTable {
+    /// filtered and sorted
+    pub rows: Vec<TorrentRow>,
+    /// before filtering
+    pub total: usize,
+    pub view: TorrentView,
+}
+
+impl TorrentTable {
+    pub fn new(all: &[TorrentRow], view: &TorrentView) -> TorrentTable {
+        TorrentTable {
+            rows: view.apply(all),
+            total: all.len(),
+            view: view.clone(),
+        }
+    }
+}
+
+pub fn drawTorrents<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    x: &Option<TorrentTable>,
+    detail: &Option<TorrentDetail>,
+) {
+    let x = match x {
+        Some(x) => x,
+        None => {
+            let table = Table::new(vec![Row::new(vec![Cell::from(Span::raw("loading"))])])
+                .block(Block::default().title("Torrents").borders(Borders::ALL))
+                .widths(&[Constraint::Percentage(100)]);
+            f.render_widget(table, area);
+            return;
+        }
+    };
+    let view = &x.view;
+    let area = if view.detail {
+        let chunks = Layout::default()
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+            .direction(Direction::Vertical)
+            .split(area);
+        // until the selected torrent is fetched
+        let detail = detail
+            .as_ref()
+            .filter(|x| x.torrent.hash_string == view.selected)
+            .cloned();
+        drawDetail(f, chunks[1], &detail);
+        chunks[0]
+    } else {
+        area
+    };
+    let mut title = format!("Torrents {}/{}", x.rows.len(), x.total);
+    if let Some(status) = view.status {
+        title.push_str(&format!(" [status {}]", statusName(status)));
+    }
+    if let Some(ref category) = view.category {
+        title.push_str(&format!(" [category {}]", category));
+    }
+    if view.editing || !view.text.is_empty() {
+        title.push_str(&format!(
+            " [/{}{}]",
+            view.text,
+            if view.editing { "_" } else { "" }
+        ));
+    }
+    title.push_str(if view.detail {
+        " Esc close "
+    } else {
+        " Enter detail s/S sort r reverse f status c category / search Esc clear "
+    });
+    title.push_str(actions::KEYS);
+
+    let header = Row::new(COLUMNS.iter().map(|c| {
+        if *c == view.sort {
+            Cell::from(Span::styled(
+                format!("{}{}", c.title(), if view.descending { "▼" } else { "▲" }),
+                Style::default().fg(Color::Yellow),
+            ))
+        } else {
+            Cell::from(Span::raw(c.title()))
+        }
+    }));
+    let items: Vec<Row> = x
+        .rows
+        .iter()
+        .map(|row| Row::new(COLUMNS.iter().map(|c| Cell::from(Span::raw(c.cell(row))))))
+        .collect();
+    let table = Table::new(items)
+        .header(header)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&WIDTHS);
+    let mut state = TableState::default();
+    state.select(view.position(&x.rows));
+    f.render_stateful_widget(table, area, &mut state);
+    if let Some(ref x) = view.confirm {
+        drawConfirm(f, area, &x.describe());
+    }
+}